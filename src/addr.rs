@@ -0,0 +1,69 @@
+use std::fmt;
+use std::path::Path;
+
+#[cfg(target_os = "windows")]
+type Inner = mio_uds_windows::net::SocketAddr;
+#[cfg(not(target_os = "windows"))]
+type Inner = std::os::unix::net::SocketAddr;
+
+/// An address associated with a Unix socket.
+///
+/// This is the same type on every platform, whichever backend produced it.
+#[derive(Clone)]
+pub struct SocketAddr {
+    inner: Inner
+}
+
+impl SocketAddr {
+    /// Returns the contents of this address if it is a pathname address.
+    pub fn as_pathname(&self) -> Option<&Path> {
+        self.inner.as_pathname()
+    }
+
+    /// Returns `true` if the address is unnamed.
+    ///
+    /// Abstract addresses are not considered unnamed.
+    pub fn is_unnamed(&self) -> bool {
+        self.inner.as_pathname().is_none() && self.as_abstract_name().is_none()
+    }
+
+    /// Returns the name of this address if it lives in the abstract
+    /// namespace, without the leading NUL byte.
+    ///
+    /// Only Linux and Android have an abstract namespace; on every other
+    /// platform this always returns `None`.
+    pub fn as_abstract_name(&self) -> Option<&[u8]> {
+        #[cfg(target_os = "linux")]
+        return std::os::linux::net::SocketAddrExt::as_abstract_name(&self.inner);
+
+        #[cfg(target_os = "android")]
+        return std::os::android::net::SocketAddrExt::as_abstract_name(&self.inner);
+
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        None
+    }
+}
+
+impl From<Inner> for SocketAddr {
+    fn from(inner: Inner) -> Self {
+        SocketAddr { inner }
+    }
+}
+
+impl fmt::Debug for SocketAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl fmt::Display for SocketAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(path) = self.as_pathname() {
+            write!(f, "{}", path.display())
+        } else if let Some(name) = self.as_abstract_name() {
+            write!(f, "@{}", name.escape_ascii())
+        } else {
+            f.write_str("(unnamed)")
+        }
+    }
+}
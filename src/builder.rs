@@ -0,0 +1,181 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::UnixListener;
+
+/// The listen backlog used unless [`UnixListenerBuilder::backlog`] says
+/// otherwise.
+pub(crate) const DEFAULT_BACKLOG: i32 = 128;
+
+/// What [`UnixListenerBuilder::bind`] does about a socket file already
+/// present at the path being bound.
+///
+/// Files that are not sockets are never removed, whatever the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnlinkPolicy {
+    /// Leave the file alone, so binding fails with `AddrInUse`.
+    Never,
+    /// Remove the file only if no listener is accepting on it anymore, as
+    /// [`UnixListener::bind_replace_stale`] does.
+    IfStale,
+    /// Remove the file even if a listener is still accepting on it.
+    Always,
+}
+
+/// A builder for a [`UnixListener`] which controls how its socket file is
+/// created.
+///
+/// On Unix, the socket is bound, given its mode and ownership, and only then
+/// starts listening. Since connecting to a socket which isn't listening yet
+/// is refused, no client can ever get in under the permissions the file had
+/// for a moment after being created.
+#[derive(Debug, Clone)]
+pub struct UnixListenerBuilder {
+    mode: Option<u32>,
+    owner: Option<u32>,
+    group: Option<u32>,
+    backlog: i32,
+    create_parents: bool,
+    unlink: UnlinkPolicy,
+    remove_on_drop: bool,
+}
+
+impl Default for UnixListenerBuilder {
+    fn default() -> Self {
+        UnixListenerBuilder {
+            mode: None,
+            owner: None,
+            group: None,
+            backlog: DEFAULT_BACKLOG,
+            create_parents: false,
+            unlink: UnlinkPolicy::Never,
+            remove_on_drop: false,
+        }
+    }
+}
+
+impl UnixListenerBuilder {
+    /// Creates a builder which binds like [`UnixListener::bind`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the permission bits of the socket file, regardless of the umask.
+    ///
+    /// Only supported on Unix.
+    pub fn mode(&mut self, mode: u32) -> &mut Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Sets the user owning the socket file.
+    ///
+    /// Only supported on Unix.
+    pub fn owner(&mut self, uid: u32) -> &mut Self {
+        self.owner = Some(uid);
+        self
+    }
+
+    /// Sets the group owning the socket file.
+    ///
+    /// Only supported on Unix.
+    pub fn group(&mut self, gid: u32) -> &mut Self {
+        self.group = Some(gid);
+        self
+    }
+
+    /// Sets the maximum number of pending connections the kernel queues for
+    /// the listener. Defaults to 128.
+    ///
+    /// Ignored on Windows, whose backend always uses its own default.
+    pub fn backlog(&mut self, backlog: i32) -> &mut Self {
+        self.backlog = backlog;
+        self
+    }
+
+    /// Sets whether missing parent directories of the socket path are
+    /// created. Off by default.
+    pub fn create_parents(&mut self, create: bool) -> &mut Self {
+        self.create_parents = create;
+        self
+    }
+
+    /// Sets what happens to a socket file already present at the path.
+    /// Defaults to [`UnlinkPolicy::Never`].
+    pub fn unlink_existing(&mut self, policy: UnlinkPolicy) -> &mut Self {
+        self.unlink = policy;
+        self
+    }
+
+    /// Sets whether the built listener removes its socket file when dropped,
+    /// see [`UnixListener::set_remove_on_drop`]. Off by default.
+    pub fn remove_on_drop(&mut self, remove: bool) -> &mut Self {
+        self.remove_on_drop = remove;
+        self
+    }
+
+    /// Creates a new listener bound to `path` with this configuration.
+    pub fn bind<P: AsRef<Path>>(&self, path: P) -> io::Result<UnixListener> {
+        let path = path.as_ref();
+
+        #[cfg(target_os = "windows")]
+        {
+            if self.mode.is_some() || self.owner.is_some() || self.group.is_some() {
+                return Err(io::Error::new(io::ErrorKind::Unsupported,
+                                          "socket file mode and ownership are not supported on Windows"));
+            }
+        }
+
+        if self.create_parents {
+            if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        match self.unlink {
+            UnlinkPolicy::Never => {}
+            UnlinkPolicy::IfStale => crate::socket_file::remove_stale(path)?,
+            UnlinkPolicy::Always => crate::socket_file::remove_any(path)?,
+        }
+
+        let mut listener = self.bind_socket(path)?;
+        listener.set_remove_on_drop(self.remove_on_drop);
+        Ok(listener)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn bind_socket(&self, path: &Path) -> io::Result<UnixListener> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let fd = crate::sys::socket(libc::SOCK_STREAM)?;
+        crate::sys::bind(&fd, path.as_os_str().as_bytes())?;
+
+        let configured = self.configure_file(path)
+            .and_then(|()| crate::sys::listen(&fd, self.backlog));
+        if let Err(err) = configured {
+            let _ = fs::remove_file(path);
+            return Err(err);
+        }
+
+        UnixListener::from_std_listener(fd.into(), Some(path))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn configure_file(&self, path: &Path) -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        if let Some(mode) = self.mode {
+            fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+        }
+        if self.owner.is_some() || self.group.is_some() {
+            std::os::unix::fs::chown(path, self.owner, self.group)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    fn bind_socket(&self, path: &Path) -> io::Result<UnixListener> {
+        UnixListener::bind(path)
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::hash_map::RandomState;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::UnixStream;
+
+/// The longest tokio's timer is trusted to wait. Timeouts beyond it count as
+/// no timeout at all, and backoff delays are cut short to it.
+const MAX_WAIT: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Options for connecting a [`UnixStream`] to a server which may not be
+/// ready yet.
+///
+/// Attempts which fail with `ErrorKind::NotFound`, because the socket file
+/// hasn't been created yet, or `ErrorKind::ConnectionRefused`, because nobody
+/// is listening on it yet, are retried after a delay. The delay starts at
+/// the initial backoff and doubles after every attempt, up to the maximum
+/// backoff; each one is randomly shortened by up to half, so clients started
+/// together don't retry in lockstep.
+///
+/// ```no_run
+/// # async fn doc() -> std::io::Result<()> {
+/// use std::time::Duration;
+/// use tokio_agnostic_uds::ConnectOptions;
+///
+/// let stream = ConnectOptions::new()
+///     .timeout(Some(Duration::from_secs(5)))
+///     .connect("/run/app.sock")
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ConnectOptions {
+    timeout: Option<Duration>,
+    attempt_timeout: Option<Duration>,
+    max_attempts: Option<u32>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl ConnectOptions {
+    /// Creates options which make up to 5 attempts, backing off from 50
+    /// milliseconds up to 2 seconds, without any timeouts.
+    pub fn new() -> Self {
+        ConnectOptions {
+            timeout: None,
+            attempt_timeout: None,
+            max_attempts: Some(5),
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+
+    /// Sets how long connecting may take overall, including the delays
+    /// between attempts.
+    ///
+    /// Once it runs out, connecting fails with `ErrorKind::TimedOut`.
+    /// Timeouts of a year or more, such as `Duration::MAX`, mean no timeout.
+    pub fn timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how long a single attempt may take.
+    ///
+    /// An attempt which runs out of time fails with `ErrorKind::TimedOut`
+    /// and is retried like a refused one. As with the overall
+    /// [`timeout`](ConnectOptions::timeout), a year or more means no limit.
+    pub fn attempt_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.attempt_timeout = timeout;
+        self
+    }
+
+    /// Sets how many attempts are made at most, or, with `None`, keeps
+    /// retrying until the overall [`timeout`](ConnectOptions::timeout) runs
+    /// out.
+    ///
+    /// An attempt count of zero is treated as one.
+    pub fn max_attempts(&mut self, max_attempts: Option<u32>) -> &mut Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the delay before the first retry, and the longest delay the
+    /// exponential backoff grows to.
+    pub fn backoff(&mut self, initial: Duration, max: Duration) -> &mut Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Connects to the socket at `path`, retrying as configured.
+    ///
+    /// If every attempt fails, the error has the kind of the last failure,
+    /// or `ErrorKind::TimedOut` if the overall timeout ran out, and wraps a
+    /// [`ConnectError`] listing why each attempt failed.
+    pub async fn connect<P: AsRef<Path>>(&self, path: P) -> io::Result<UnixStream> {
+        let path = path.as_ref();
+        self.retry(path.display(), || UnixStream::connect(path)).await
+    }
+
+    /// Connects to the socket named `name` in the abstract namespace,
+    /// retrying as configured, see [`connect`](ConnectOptions::connect) and
+    /// [`UnixStream::connect_abstract`].
+    pub async fn connect_abstract<N: AsRef<[u8]>>(&self, name: N) -> io::Result<UnixStream> {
+        let name = name.as_ref();
+        let target = format!("@{}", name.escape_ascii());
+        self.retry(target, || UnixStream::connect_abstract(name)).await
+    }
+
+    async fn retry<T, F, Fut>(&self, target: T, mut connect: F) -> io::Result<UnixStream>
+    where
+        T: fmt::Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = io::Result<UnixStream>>,
+    {
+        let deadline = self.timeout
+            .filter(|&timeout| timeout < MAX_WAIT)
+            .and_then(|timeout| Instant::now().checked_add(timeout));
+        let attempt_timeout = self.attempt_timeout.filter(|&timeout| timeout < MAX_WAIT);
+        let max_attempts = self.max_attempts.map(|max| max.max(1) as usize);
+        let mut backoff = self.initial_backoff;
+        let mut errors = Vec::new();
+
+        let timed_out = loop {
+            let limit = match (attempt_timeout, deadline) {
+                (Some(timeout), Some(deadline)) => Some(timeout.min(deadline.saturating_duration_since(Instant::now()))),
+                (Some(timeout), None) => Some(timeout),
+                (None, Some(deadline)) => Some(deadline.saturating_duration_since(Instant::now())),
+                (None, None) => None,
+            };
+            let res = match limit {
+                Some(limit) => tokio::time::timeout(limit, connect()).await
+                    .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "connection attempt timed out"))),
+                None => connect().await,
+            };
+
+            let err = match res {
+                Ok(stream) => return Ok(stream),
+                Err(err) => err,
+            };
+            let retry = is_retryable(&err);
+            errors.push(err);
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break true;
+            }
+            if !retry || max_attempts.is_some_and(|max| errors.len() >= max) {
+                break false;
+            }
+
+            let delay = jitter(backoff).min(MAX_WAIT);
+            backoff = backoff.checked_mul(2).unwrap_or(self.max_backoff).min(self.max_backoff);
+            if deadline.is_some_and(|deadline| Instant::now().checked_add(delay).map_or(true, |wake| wake >= deadline)) {
+                break true;
+            }
+            log::debug!("failed to connect to {}: {}; retrying in {:?}", target, errors[errors.len() - 1], delay);
+            tokio::time::delay_for(delay).await;
+        };
+
+        let kind = if timed_out { io::ErrorKind::TimedOut } else { errors[errors.len() - 1].kind() };
+        Err(io::Error::new(kind, ConnectError { target: target.to_string(), attempts: errors }))
+    }
+}
+
+impl Default for ConnectOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns `true` for the errors of an attempt to connect to a server which
+/// may simply not be up yet.
+fn is_retryable(err: &io::Error) -> bool {
+    matches!(err.kind(), io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused | io::ErrorKind::TimedOut)
+}
+
+/// Picks a delay between half of `backoff` and all of it.
+fn jitter(backoff: Duration) -> Duration {
+    // Every `RandomState` is seeded differently, which is random enough here.
+    let random = RandomState::new().build_hasher().finish();
+    let half = backoff / 2;
+    let range = u64::try_from(half.as_nanos()).unwrap_or(u64::MAX).saturating_add(1);
+    half + Duration::from_nanos(random % range)
+}
+
+/// Why connecting with [`ConnectOptions`] failed: the error of every attempt
+/// made, in order.
+///
+/// Carried inside the `io::Error` returned by
+/// [`ConnectOptions::connect`], from which it can be recovered with
+/// `get_ref` and `downcast_ref`.
+#[derive(Debug)]
+pub struct ConnectError {
+    target: String,
+    attempts: Vec<io::Error>,
+}
+
+impl ConnectError {
+    /// Returns the errors of the attempts made, in order.
+    pub fn attempts(&self) -> &[io::Error] {
+        &self.attempts
+    }
+}
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to connect to {} after {} attempt{}", self.target, self.attempts.len(),
+               if self.attempts.len() == 1 { "" } else { "s" })?;
+        for (i, err) in self.attempts.iter().enumerate() {
+            write!(f, "{} attempt {}: {}", if i == 0 { ":" } else { ";" }, i + 1, err)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConnectError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.attempts.last().map(|err| err as &(dyn Error + 'static))
+    }
+}
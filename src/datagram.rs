@@ -0,0 +1,202 @@
+#[cfg(not(target_os = "windows"))]
+pub(crate) use self::unix::UnixDatagram;
+#[cfg(target_os = "windows")]
+pub(crate) use self::windows::UnixDatagram;
+
+#[cfg(not(target_os = "windows"))]
+mod unix {
+    use tokio::io::PollEvented;
+    use futures::task::{Poll, Context};
+
+    use crate::ready::PollSocket;
+
+    use std::fmt;
+    use std::io;
+    use std::net::Shutdown;
+    use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+    use std::os::unix::net::{self, SocketAddr};
+    use std::path::Path;
+
+    /// A Unix datagram socket.
+    ///
+    /// This wraps mio-uds directly, like `stream.rs` does, rather than
+    /// `tokio::net::UnixDatagram`: tokio 0.2 offers no way to wait for
+    /// readiness and then issue our own `sendmsg`/`recvmsg`, which passing
+    /// descriptors and credentials needs, nor a way back to std.
+    pub struct UnixDatagram {
+        io: PollEvented<mio_uds::UnixDatagram>,
+    }
+
+    impl UnixDatagram {
+        /// Creates a new `UnixDatagram` bound to the specified path.
+        pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<UnixDatagram> {
+            UnixDatagram::new(mio_uds::UnixDatagram::bind(path)?)
+        }
+
+        /// Creates a new `UnixDatagram` which is not bound to any address.
+        pub fn unbound() -> io::Result<UnixDatagram> {
+            UnixDatagram::new(mio_uds::UnixDatagram::unbound()?)
+        }
+
+        /// Creates an unnamed pair of connected sockets.
+        pub fn pair() -> io::Result<(UnixDatagram, UnixDatagram)> {
+            let (a, b) = mio_uds::UnixDatagram::pair()?;
+            Ok((UnixDatagram::new(a)?, UnixDatagram::new(b)?))
+        }
+
+        /// Consumes a `UnixDatagram` in the standard library and returns a
+        /// nonblocking `UnixDatagram` from this crate.
+        pub fn from_std(socket: net::UnixDatagram) -> io::Result<UnixDatagram> {
+            UnixDatagram::new(mio_uds::UnixDatagram::from_datagram(socket)?)
+        }
+
+        /// Consumes this socket, returning the `UnixDatagram` from the
+        /// standard library it wraps.
+        ///
+        /// The returned socket is still in nonblocking mode.
+        pub fn into_std(self) -> io::Result<net::UnixDatagram> {
+            let socket = self.io.into_inner()?;
+            Ok(unsafe { net::UnixDatagram::from_raw_fd(socket.into_raw_fd()) })
+        }
+
+        fn new(socket: mio_uds::UnixDatagram) -> io::Result<UnixDatagram> {
+            let io = PollEvented::new(socket)?;
+            Ok(UnixDatagram { io })
+        }
+
+        /// Connects the socket to the specified address.
+        pub fn connect<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+            self.io.get_ref().connect(path)
+        }
+
+        /// Returns the local address this socket is bound to.
+        pub fn local_addr(&self) -> io::Result<SocketAddr> {
+            self.io.get_ref().local_addr()
+        }
+
+        /// Returns the address of this socket's peer.
+        pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+            self.io.get_ref().peer_addr()
+        }
+
+        /// Returns the value of the `SO_ERROR` option.
+        pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+            self.io.get_ref().take_error()
+        }
+
+        /// Shuts down the read, write, or both halves of this socket.
+        pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+            self.io.get_ref().shutdown(how)
+        }
+
+        /// Sends data on the socket to its connected peer.
+        pub fn poll_send(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+            self.poll_write_with(cx, |socket| socket.send(buf))
+        }
+
+        /// Sends data on the socket to the socket at `path`.
+        pub fn poll_send_to(&self, cx: &mut Context<'_>, buf: &[u8], path: &Path) -> Poll<io::Result<usize>> {
+            self.poll_write_with(cx, |socket| socket.send_to(buf, path))
+        }
+
+        /// Receives data from the socket.
+        pub fn poll_recv(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+            self.poll_read_with(cx, |socket| socket.recv(buf))
+        }
+
+        /// Receives data from the socket, along with the address it came
+        /// from.
+        pub fn poll_recv_from(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<(usize, SocketAddr)>> {
+            self.poll_read_with(cx, |socket| socket.recv_from(buf))
+        }
+    }
+
+    impl PollSocket for UnixDatagram {
+        type Socket = mio_uds::UnixDatagram;
+
+        fn poll_evented(&self) -> &PollEvented<mio_uds::UnixDatagram> {
+            &self.io
+        }
+    }
+
+    impl fmt::Debug for UnixDatagram {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.io.get_ref().fmt(f)
+        }
+    }
+
+    impl AsRawFd for UnixDatagram {
+        fn as_raw_fd(&self) -> RawFd {
+            self.io.get_ref().as_raw_fd()
+        }
+    }
+}
+
+/// Windows has no datagram Unix sockets, so this type can never be created;
+/// it only lets the agnostic wrapper compile everywhere.
+#[cfg(target_os = "windows")]
+mod windows {
+    use futures::task::{Poll, Context};
+    use mio_uds_windows::net::SocketAddr;
+
+    use std::io;
+    use std::net::Shutdown;
+    use std::path::Path;
+
+    #[derive(Debug)]
+    pub enum UnixDatagram {}
+
+    impl UnixDatagram {
+        pub fn bind<P: AsRef<Path>>(_path: P) -> io::Result<UnixDatagram> {
+            Err(unsupported())
+        }
+
+        pub fn unbound() -> io::Result<UnixDatagram> {
+            Err(unsupported())
+        }
+
+        pub fn pair() -> io::Result<(UnixDatagram, UnixDatagram)> {
+            Err(unsupported())
+        }
+
+        pub fn connect<P: AsRef<Path>>(&self, _path: P) -> io::Result<()> {
+            match *self {}
+        }
+
+        pub fn local_addr(&self) -> io::Result<SocketAddr> {
+            match *self {}
+        }
+
+        pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+            match *self {}
+        }
+
+        pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+            match *self {}
+        }
+
+        pub fn shutdown(&self, _how: Shutdown) -> io::Result<()> {
+            match *self {}
+        }
+
+        pub fn poll_send(&self, _cx: &mut Context<'_>, _buf: &[u8]) -> Poll<io::Result<usize>> {
+            match *self {}
+        }
+
+        pub fn poll_send_to(&self, _cx: &mut Context<'_>, _buf: &[u8], _path: &Path) -> Poll<io::Result<usize>> {
+            match *self {}
+        }
+
+        pub fn poll_recv(&self, _cx: &mut Context<'_>, _buf: &mut [u8]) -> Poll<io::Result<usize>> {
+            match *self {}
+        }
+
+        pub fn poll_recv_from(&self, _cx: &mut Context<'_>, _buf: &mut [u8]) -> Poll<io::Result<(usize, SocketAddr)>> {
+            match *self {}
+        }
+    }
+
+    fn unsupported() -> io::Error {
+        io::Error::new(io::ErrorKind::Unsupported, "datagram sockets are not supported on Windows")
+    }
+}
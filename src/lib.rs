@@ -1,22 +1,40 @@
-#![doc(html_root_url = "https://docs.rs/tokio-uds/0.2.3")]
-#![deny(missing_debug_implementations)]
-
-//! Unix Domain Sockets for Tokio.
-//!
-//! This crate provides APIs for using Unix Domain Sockets with Tokio.
-#[cfg(target_os = "windows")]
-mod incoming;
-#[cfg(target_os = "windows")]
-mod listener;
-#[cfg(target_os = "windows")]
-mod stream;
-mod merge;
-
-#[cfg(target_os = "windows")]
-pub(crate) mod windows {
-    pub use crate::incoming::Incoming;
-    pub use crate::listener::UnixListener;
-    pub use crate::stream::{UnixStream, ConnectFuture};
-}
-
-pub use merge::{UnixStream, UnixListener};
\ No newline at end of file
+#![doc(html_root_url = "https://docs.rs/tokio-uds/0.2.3")]
+#![deny(missing_debug_implementations)]
+
+//! Unix Domain Sockets for Tokio.
+//!
+//! This crate provides APIs for using Unix Domain Sockets with Tokio.
+mod listener;
+mod stream;
+mod datagram;
+mod merge;
+mod addr;
+mod socket_file;
+mod builder;
+mod ucred;
+mod policy;
+mod shutdown;
+mod limit;
+mod split;
+mod connect;
+#[cfg(unix)]
+mod seqpacket;
+#[cfg(unix)]
+mod systemd;
+#[cfg(unix)]
+mod sys;
+#[cfg(unix)]
+mod ready;
+
+pub use merge::{UnixStream, UnixListener, UnixDatagram, Incoming, is_transient_accept_error};
+pub use addr::SocketAddr;
+pub use builder::{UnixListenerBuilder, UnlinkPolicy};
+pub use ucred::UCred;
+pub use policy::AccessPolicy;
+pub use shutdown::ShutdownHandle;
+pub use connect::{ConnectOptions, ConnectError};
+pub use split::{ReadHalf, WriteHalf, OwnedReadHalf, OwnedWriteHalf, ReuniteError};
+#[cfg(unix)]
+pub use seqpacket::{UnixSeqpacketListener, UnixSeqpacket};
+#[cfg(unix)]
+pub use systemd::{ActivatedSockets, Notifier};
\ No newline at end of file
@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use futures::task::{AtomicWaker, Context, Poll};
+
+/// Counts the live connections accepted by a listener, against an optional
+/// maximum.
+#[derive(Debug)]
+pub(crate) struct ConnectionLimit {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    live: AtomicUsize,
+    max: AtomicUsize,
+    listener: AtomicWaker,
+}
+
+impl ConnectionLimit {
+    pub(crate) fn new() -> Self {
+        ConnectionLimit {
+            inner: Arc::new(Inner {
+                live: AtomicUsize::new(0),
+                max: AtomicUsize::new(usize::MAX),
+                listener: AtomicWaker::new(),
+            })
+        }
+    }
+
+    pub(crate) fn live(&self) -> usize {
+        self.inner.live.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_max(&self, max: usize) {
+        self.inner.max.store(max, Ordering::Release);
+        self.inner.listener.wake();
+    }
+
+    /// Ready while fewer connections than the maximum are live; otherwise
+    /// the task is woken once one of them is dropped.
+    pub(crate) fn poll_available(&self, cx: &mut Context<'_>) -> Poll<()> {
+        self.inner.listener.register(cx.waker());
+        if self.live() < self.inner.max.load(Ordering::Acquire) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Counts a newly accepted connection until the returned guard is dropped.
+    pub(crate) fn acquire(&self) -> ConnectionGuard {
+        self.inner.live.fetch_add(1, Ordering::AcqRel);
+        ConnectionGuard { inner: self.inner.clone() }
+    }
+}
+
+/// Held by an accepted stream, so it counts as live until dropped.
+#[derive(Debug)]
+pub(crate) struct ConnectionGuard {
+    inner: Arc<Inner>,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let live = self.inner.live.fetch_sub(1, Ordering::AcqRel);
+        if live >= self.inner.max.load(Ordering::Acquire) {
+            self.inner.listener.wake();
+        }
+    }
+}
@@ -1,116 +1,960 @@
-use std::path::Path;
-use tokio::io::{AsyncRead, AsyncWrite};
-use futures::task::{Context, Poll};
-use tokio::macros::support::Pin;
-use futures::io::Error;
-use futures::Stream;
-use pin_project::pin_project;
-
-#[cfg(target_os = "windows")]
-#[derive(Debug)]
-pub struct UnixListener {
-    inner: crate::windows::UnixListener
-}
-
-#[cfg(not(target_os = "windows"))]
-#[derive(Debug)]
-pub struct UnixListener {
-    inner: tokio::net::UnixListener
-}
-
-impl UnixListener {
-    #[cfg(target_os = "windows")]
-    pub fn bind<P: AsRef<Path>>(bind_path: P) -> std::io::Result<Self> {
-        crate::listener::UnixListener::bind(bind_path)
-            .map(|inner| UnixListener { inner })
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    pub fn bind<P: AsRef<Path>>(bind_path: P) -> std::io::Result<Self> {
-        tokio::net::UnixListener::bind(bind_path)
-            .map(|inner| UnixListener { inner })
-    }
-
-    #[cfg(target_os = "windows")]
-    pub fn incoming(self) -> crate::incoming::Incoming {
-        self.inner.incoming()
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    pub fn incoming<'a>(&'a mut self) -> tokio::net::unix::Incoming<'a> {
-        self.inner.incoming()
-    }
-
-}
-
-impl Stream for UnixListener {
-    #[cfg(target_os = "windows")]
-    type Item = (UnixStream, Option<mio_uds_windows::net::SocketAddr>);
-    #[cfg(not(target_os = "windows"))]
-    type Item = (UnixStream, Option<std::os::unix::net::SocketAddr>);
-
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        #[cfg(target_os = "windows")]
-        return Poll::Ready(futures::ready!(self.inner.poll_accept(cx))
-            .map(|(inner, addr)|(UnixStream { inner }, Some(addr))));
-
-        #[cfg(not(target_os = "windows"))] {
-            match futures::ready!(Pin::new(&mut self.get_mut().incoming()).poll_accept(cx)) {
-                Ok(inner) => {
-                    let addr = inner.peer_addr().ok();
-                    Poll::Ready(Some((UnixStream { inner }, addr)))
-                }
-
-                Err(_) => Poll::Ready(None)
-            }
-        }
-    }
-}
-
-#[cfg(target_os = "windows")]
-#[pin_project]
-#[derive(Debug)]
-pub struct UnixStream {
-    #[pin]
-    inner: crate::windows::UnixStream
-}
-
-#[cfg(not(target_os = "windows"))]
-#[pin_project]
-#[derive(Debug)]
-pub struct UnixStream {
-    #[pin]
-    inner: tokio::net::UnixStream
-}
-
-impl UnixStream {
-    #[cfg(target_os = "windows")]
-    pub async fn connect<P: AsRef<Path>>(bind_path: P) -> std::io::Result<Self> {
-        crate::stream::UnixStream::connect(bind_path)?.await.map(|inner| Self { inner })
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    pub async fn connect<P: AsRef<Path>>(bind_path: P) -> std::io::Result<Self> {
-        tokio::net::UnixStream::connect(bind_path).await.map(|inner| Self { inner })
-    }
-}
-
-impl AsyncRead for UnixStream {
-    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
-        self.project().inner.poll_read(cx, buf)
-    }
-}
-
-impl AsyncWrite for UnixStream {
-    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize, Error>> {
-        self.project().inner.poll_write(cx, buf)
-    }
-
-    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
-        self.project().inner.poll_flush(cx)
-    }
-
-    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
-        self.project().inner.poll_shutdown(cx)
-    }
-}
\ No newline at end of file
+use std::io::{self, IoSlice, IoSliceMut};
+use std::net::Shutdown;
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use futures::task::{Context, Poll};
+use tokio::macros::support::Pin;
+use futures::io::Error;
+use bytes::{Buf, BufMut};
+use futures::{Future, Stream};
+use tokio::time::Delay;
+use pin_project::pin_project;
+use crate::{AccessPolicy, SocketAddr, UCred};
+use crate::socket_file::SocketFile;
+use crate::ShutdownHandle;
+use crate::limit::{ConnectionGuard, ConnectionLimit};
+use crate::split::{OwnedReadHalf, OwnedWriteHalf, ReadHalf, WriteHalf};
+#[cfg(not(target_os = "windows"))]
+use std::os::unix::io::{OwnedFd, RawFd};
+#[cfg(not(target_os = "windows"))]
+use crate::ready::PollSocket;
+#[cfg(target_os = "windows")]
+use std::os::windows::io::{OwnedSocket, RawSocket};
+
+/// How long a listener waits by default after running out of resources,
+/// see [`UnixListener::set_accept_backoff`].
+const DEFAULT_ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub struct UnixListener {
+    inner: crate::listener::UnixListener,
+    file: Option<SocketFile>,
+    policy: Option<AccessPolicy>,
+    shutdown: Option<ShutdownHandle>,
+    limit: ConnectionLimit,
+    backoff: Option<Duration>,
+    retry_at: Option<Delay>
+}
+
+impl UnixListener {
+    fn from_inner(inner: crate::listener::UnixListener, file: Option<SocketFile>) -> Self {
+        UnixListener {
+            inner,
+            file,
+            policy: None,
+            shutdown: None,
+            limit: ConnectionLimit::new(),
+            backoff: Some(DEFAULT_ACCEPT_BACKOFF),
+            retry_at: None
+        }
+    }
+
+    pub fn bind<P: AsRef<Path>>(bind_path: P) -> std::io::Result<Self> {
+        let inner = crate::listener::UnixListener::bind(bind_path.as_ref())?;
+        Ok(Self::from_inner(inner, SocketFile::new(bind_path.as_ref()).ok()))
+    }
+
+    /// Wraps a socket which is already listening, taking charge of its socket
+    /// file at `path` if it has one.
+    #[cfg(not(target_os = "windows"))]
+    pub(crate) fn from_std_listener(listener: std::os::unix::net::UnixListener, path: Option<&Path>) -> io::Result<Self> {
+        let inner = crate::listener::UnixListener::from_std(listener)?;
+        Ok(Self::from_inner(inner, path.and_then(|path| SocketFile::new(path).ok())))
+    }
+
+    /// Creates a new listener from a `UnixListener` in the standard library
+    /// which is already bound and listening.
+    ///
+    /// The listener is moved into nonblocking mode. If it is bound to a
+    /// filesystem path, that socket file can be removed on drop, see
+    /// [`set_remove_on_drop`](UnixListener::set_remove_on_drop).
+    #[cfg(not(target_os = "windows"))]
+    pub fn from_std(listener: std::os::unix::net::UnixListener) -> io::Result<Self> {
+        let addr = listener.local_addr()?;
+        Self::from_std_listener(listener, addr.as_pathname())
+    }
+
+    /// Creates a new listener from a `UnixListener` from `uds_windows` which
+    /// is already bound and listening.
+    #[cfg(target_os = "windows")]
+    pub fn from_std(listener: mio_uds_windows::net::UnixListener) -> io::Result<Self> {
+        crate::listener::UnixListener::from_std(listener).map(|inner| Self::from_inner(inner, None))
+    }
+
+    /// Consumes this listener, returning the `UnixListener` from the standard
+    /// library it wraps, still in nonblocking mode.
+    ///
+    /// The access policy, connection limit and shutdown handle stay behind,
+    /// and the socket file is no longer removed on drop.
+    #[cfg(not(target_os = "windows"))]
+    pub fn into_std(mut self) -> io::Result<std::os::unix::net::UnixListener> {
+        if let Some(file) = self.file.as_mut() {
+            file.set_remove_on_drop(false);
+        }
+        self.inner.into_std()
+    }
+
+    /// Creates a new listener bound to `name` in the abstract namespace.
+    ///
+    /// `name` is given without the leading NUL byte. Abstract sockets have
+    /// no filesystem entry, so there is nothing to clean up or secure.
+    ///
+    /// Only Linux and Android have an abstract namespace; elsewhere this
+    /// fails with `ErrorKind::Unsupported`.
+    pub fn bind_abstract<N: AsRef<[u8]>>(name: N) -> io::Result<Self> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            let fd = crate::sys::socket(libc::SOCK_STREAM)?;
+            crate::sys::bind(&fd, &crate::sys::abstract_name(name.as_ref()))?;
+            crate::sys::listen(&fd, crate::builder::DEFAULT_BACKLOG)?;
+            Self::from_std_listener(fd.into(), None)
+        }
+
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            let _ = name;
+            Err(abstract_unsupported())
+        }
+    }
+
+    /// Creates a new listener bound to `bind_path`, first removing a socket
+    /// file left behind there by a listener that no longer exists.
+    ///
+    /// The existing file is only removed if it is a socket and a connection
+    /// attempt to it is refused. If a listener accepts that attempt, this
+    /// fails with `ErrorKind::AddrInUse`; if the file is not a socket, it
+    /// fails with `ErrorKind::AlreadyExists`. In both cases the file is left
+    /// alone.
+    ///
+    /// A live listener sees the probe as a connection which is closed again
+    /// without sending anything, so its accept loop should expect that.
+    pub fn bind_replace_stale<P: AsRef<Path>>(bind_path: P) -> io::Result<Self> {
+        crate::socket_file::remove_stale(bind_path.as_ref())?;
+        Self::bind(bind_path)
+    }
+
+    /// Sets whether the socket file this listener was bound to is removed
+    /// when the listener is dropped. Off by default.
+    ///
+    /// The file is only removed if it is still the one this listener
+    /// created, so a socket bound at the same path by a successor survives.
+    /// Listeners not bound to a filesystem path are unaffected.
+    pub fn set_remove_on_drop(&mut self, remove: bool) {
+        if let Some(file) = self.file.as_mut() {
+            file.set_remove_on_drop(remove);
+        }
+    }
+
+    /// Closes this listener and removes the socket file it was bound to,
+    /// unless that file has been replaced since.
+    pub fn close(mut self) -> io::Result<()> {
+        let file = self.file.take();
+        drop(self);
+
+        match file {
+            Some(file) => file.remove(),
+            None => Ok(())
+        }
+    }
+
+    /// Returns a stream over the connections being received on this
+    /// listener.
+    ///
+    /// The returned stream borrows the listener and only ends once the
+    /// listener is shut down; each item is the result of one accept, exactly
+    /// as from `accept`.
+    pub fn incoming(&mut self) -> Incoming<'_> {
+        Incoming { inner: self }
+    }
+
+    /// Returns the local socket address of this listener.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr().map(SocketAddr::from)
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.inner.take_error()
+    }
+
+    /// Accepts a new incoming connection to this listener.
+    ///
+    /// Resolves to the connected stream together with the address of its
+    /// peer. Dropping the returned future before it completes is safe; no
+    /// connection is lost.
+    pub async fn accept(&mut self) -> io::Result<(UnixStream, SocketAddr)> {
+        futures::future::poll_fn(|cx| self.poll_accept(cx)).await
+    }
+
+    /// Polls to accept a new incoming connection to this listener.
+    ///
+    /// If no connection is pending, the current task is registered to be
+    /// woken once one arrives and `Poll::Pending` is returned.
+    ///
+    /// Once the listener has been shut down through its
+    /// [`ShutdownHandle`], this fails with `ErrorKind::NotConnected`.
+    pub fn poll_accept(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<(UnixStream, SocketAddr)>> {
+        match futures::ready!(self.poll_next_conn(cx)) {
+            Some(res) => Poll::Ready(res),
+            None => Poll::Ready(Err(io::Error::new(io::ErrorKind::NotConnected, "listener has been shut down")))
+        }
+    }
+
+    /// Shared by `poll_accept` and the streams, which end on shutdown.
+    fn poll_next_conn(&mut self, cx: &mut Context<'_>) -> Poll<Option<io::Result<(UnixStream, SocketAddr)>>> {
+        if let Some(shutdown) = self.shutdown.as_ref() {
+            if shutdown.poll_triggered(cx).is_ready() {
+                return Poll::Ready(None);
+            }
+        }
+
+        // At the limit, pending connections are left in the kernel's backlog.
+        futures::ready!(self.limit.poll_available(cx));
+
+        loop {
+            if let Some(retry_at) = self.retry_at.as_mut() {
+                futures::ready!(Pin::new(retry_at).poll(cx));
+                self.retry_at = None;
+            }
+
+            let (inner, addr) = match futures::ready!(self.inner.poll_accept(cx)) {
+                Ok(conn) => conn,
+                Err(err) => match self.backoff {
+                    Some(backoff) if is_resource_exhaustion(&err) => {
+                        log::warn!("failed to accept a connection: {}; retrying in {:?}", err, backoff);
+                        self.retry_at = Some(tokio::time::delay_for(backoff));
+                        continue;
+                    }
+                    _ => return Poll::Ready(Some(Err(err)))
+                }
+            };
+            let mut stream = UnixStream::from_inner(inner);
+
+            let policy = match self.policy.as_ref() {
+                Some(policy) => policy,
+                None => {
+                    stream.guard = Some(self.limit.acquire());
+                    return Poll::Ready(Some(Ok((stream, addr.into()))));
+                }
+            };
+
+            match stream.peer_cred() {
+                Ok(cred) if policy.allows(&cred) => {
+                    stream.cred = Some(cred);
+                    stream.guard = Some(self.limit.acquire());
+                    return Poll::Ready(Some(Ok((stream, addr.into()))));
+                }
+                Ok(cred) => policy.reject(&cred),
+                Err(err) => log::warn!("rejected connection with unknown credentials: {}", err)
+            }
+        }
+    }
+
+    /// Sets the maximum number of connections accepted from this listener
+    /// that may be alive at the same time.
+    ///
+    /// Once the limit is reached, the listener stops accepting and further
+    /// clients wait in the kernel's backlog until an accepted [`UnixStream`]
+    /// is dropped. Unlimited by default.
+    pub fn set_max_connections(&mut self, max: usize) {
+        self.limit.set_max(max);
+    }
+
+    /// Returns the number of connections accepted from this listener which
+    /// have not been dropped yet.
+    pub fn live_connections(&self) -> usize {
+        self.limit.live()
+    }
+
+    /// Sets how long the listener waits before accepting again after running
+    /// out of file descriptors, buffer space or memory.
+    ///
+    /// Such failures are logged at the `warn` level and retried once the
+    /// delay has passed, instead of being returned, so an accept loop can't
+    /// spin while the shortage lasts. With `None`, they are returned like
+    /// any other error. Defaults to 100 milliseconds.
+    pub fn set_accept_backoff(&mut self, backoff: Option<Duration>) {
+        self.backoff = backoff;
+    }
+
+    /// Returns a handle for shutting this listener and its connections down
+    /// gracefully.
+    ///
+    /// Every call returns a handle to the same shutdown. Once it is
+    /// initiated, the listener's `Stream` and [`Incoming`] end by yielding
+    /// `None`.
+    pub fn shutdown_handle(&mut self) -> ShutdownHandle {
+        self.shutdown.get_or_insert_with(ShutdownHandle::new).clone()
+    }
+
+    /// Sets the policy deciding which peers connections are accepted from.
+    ///
+    /// Connections from other peers are closed during accept, so they never
+    /// reach the caller. Accepted streams remember the credentials the
+    /// policy was checked against, see [`UnixStream::peer_cred`].
+    pub fn set_access_policy(&mut self, policy: AccessPolicy) {
+        self.policy = Some(policy);
+    }
+}
+
+impl Stream for UnixListener {
+    type Item = io::Result<(UnixStream, SocketAddr)>;
+
+    /// Yields each accepted connection, or the error the accept failed with.
+    ///
+    /// The stream only ends once the listener is shut down, see
+    /// [`UnixListener::shutdown_handle`]. Errors for which
+    /// [`is_transient_accept_error`] returns `true` leave the listener usable,
+    /// so the caller may simply keep polling.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_next_conn(cx)
+    }
+}
+
+/// Stream of the connections accepted by a [`UnixListener`].
+///
+/// Created by [`UnixListener::incoming`].
+#[derive(Debug)]
+pub struct Incoming<'a> {
+    inner: &'a mut UnixListener
+}
+
+impl Incoming<'_> {
+    /// Polls to accept a new incoming connection, see
+    /// [`UnixListener::poll_accept`].
+    pub fn poll_accept(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<(UnixStream, SocketAddr)>> {
+        self.inner.poll_accept(cx)
+    }
+}
+
+impl Stream for Incoming<'_> {
+    type Item = io::Result<(UnixStream, SocketAddr)>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().inner.poll_next_conn(cx)
+    }
+}
+
+/// The error for abstract namespace addresses where there is no abstract
+/// namespace.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(crate) fn abstract_unsupported() -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported,
+                   "abstract namespace sockets are only supported on Linux and Android")
+}
+
+#[cfg(target_os = "windows")]
+fn fd_passing_unsupported() -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, "passing file descriptors is not supported on Windows")
+}
+
+/// Returns `true` if `err`, as produced by accepting on a [`UnixListener`],
+/// only concerns the connection being accepted or a momentary shortage of
+/// resources, rather than the listener itself.
+///
+/// Servers should log such errors and keep accepting. Any other error means
+/// the listener is unlikely to ever accept again.
+pub fn is_transient_accept_error(err: &io::Error) -> bool {
+    match err.kind() {
+        io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::Interrupted
+        | io::ErrorKind::WouldBlock
+        | io::ErrorKind::TimedOut => true,
+        _ => is_resource_exhaustion(err)
+    }
+}
+
+/// Returns `true` if `err` reports that the process or the system ran out of
+/// descriptors, buffer space or memory.
+pub(crate) fn is_resource_exhaustion(err: &io::Error) -> bool {
+    #[cfg(target_os = "windows")]
+    const CODES: &[i32] = &[
+        10024, // WSAEMFILE
+        10055, // WSAENOBUFS
+    ];
+    #[cfg(not(target_os = "windows"))]
+    const CODES: &[i32] = &[libc::EMFILE, libc::ENFILE, libc::ENOBUFS, libc::ENOMEM];
+
+    matches!(err.raw_os_error(), Some(code) if CODES.contains(&code))
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct UnixStream {
+    #[pin]
+    inner: crate::stream::UnixStream,
+    cred: Option<UCred>,
+    guard: Option<ConnectionGuard>
+}
+
+impl UnixStream {
+    fn from_inner(inner: crate::stream::UnixStream) -> Self {
+        UnixStream { inner, cred: None, guard: None }
+    }
+
+    pub async fn connect<P: AsRef<Path>>(bind_path: P) -> std::io::Result<Self> {
+        crate::stream::UnixStream::connect(bind_path)?.await.map(Self::from_inner)
+    }
+
+    /// Creates an unnamed pair of connected streams.
+    ///
+    /// Neither end has a filesystem path. On Windows, which lacks
+    /// `socketpair`, the pair is connected through a listener bound to a
+    /// temporary path that is removed again before this returns.
+    pub fn pair() -> io::Result<(Self, Self)> {
+        let (a, b) = crate::stream::UnixStream::pair()?;
+        Ok((Self::from_inner(a), Self::from_inner(b)))
+    }
+
+    /// Creates a new stream from a connected `UnixStream` in the standard
+    /// library, moving it into nonblocking mode.
+    #[cfg(not(target_os = "windows"))]
+    pub fn from_std(stream: std::os::unix::net::UnixStream) -> io::Result<Self> {
+        crate::stream::UnixStream::from_std(stream).map(Self::from_inner)
+    }
+
+    /// Creates a new stream from a connected `UnixStream` from `uds_windows`.
+    #[cfg(target_os = "windows")]
+    pub fn from_std(stream: mio_uds_windows::net::UnixStream) -> io::Result<Self> {
+        crate::stream::UnixStream::from_std(stream).map(Self::from_inner)
+    }
+
+    /// Consumes this stream, returning the `UnixStream` from the standard
+    /// library it wraps, still in nonblocking mode.
+    ///
+    /// A stream accepted from a listener with a connection limit stops
+    /// counting towards it.
+    #[cfg(not(target_os = "windows"))]
+    pub fn into_std(self) -> io::Result<std::os::unix::net::UnixStream> {
+        self.inner.into_std()
+    }
+
+    /// Connects to the socket named `name` in the abstract namespace.
+    ///
+    /// `name` is given without the leading NUL byte. Only Linux and Android
+    /// have an abstract namespace; elsewhere this fails with
+    /// `ErrorKind::Unsupported`.
+    pub async fn connect_abstract<N: AsRef<[u8]>>(name: N) -> io::Result<Self> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            let stream = std::os::unix::net::UnixStream::from(crate::sys::socket(libc::SOCK_STREAM)?);
+            stream.set_nonblocking(true)?;
+            // Connecting a Unix socket on Linux never returns `EINPROGRESS`,
+            // so once this returns the connection is established.
+            crate::sys::connect(&stream, &crate::sys::abstract_name(name.as_ref()))?;
+            Self::from_std(stream)
+        }
+
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            let _ = name;
+            Err(abstract_unsupported())
+        }
+    }
+
+    /// Returns the socket address of the local half of this connection.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr().map(SocketAddr::from)
+    }
+
+    /// Returns the socket address of the remote half of this connection.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr().map(SocketAddr::from)
+    }
+
+    /// Returns the credentials of the process on the other end of this
+    /// connection.
+    ///
+    /// The kernel records them when the connection is made, so they are
+    /// available as soon as a stream has been accepted, before any bytes are
+    /// read. Uses `SO_PEERCRED` on Linux and Android and `getpeereid` on
+    /// macOS and the BSDs; everywhere else, including Windows, this fails
+    /// with `ErrorKind::Unsupported`.
+    ///
+    /// Streams accepted under an [`AccessPolicy`] return the credentials the
+    /// policy approved.
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        if let Some(cred) = self.cred {
+            return Ok(cred);
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        return crate::ucred::peer_cred(&self.inner);
+
+        #[cfg(target_os = "windows")]
+        Err(io::Error::new(io::ErrorKind::Unsupported,
+                           "peer credentials are not supported on Windows"))
+    }
+
+    /// Sets whether every message received on this stream carries the
+    /// credentials of its sender, `SO_PASSCRED`, for
+    /// [`recv_with_creds`](UnixStream::recv_with_creds). Off by default.
+    ///
+    /// It must be on before a message is sent for that message to carry
+    /// them. Credential passing, here and in the other `*_creds` methods of
+    /// this type and [`UnixDatagram`], is only supported on Linux and
+    /// Android; elsewhere they fail with `ErrorKind::Unsupported`.
+    pub fn set_passcred(&self, passcred: bool) -> io::Result<()> {
+        crate::ucred::set_passcred(&self.inner, passcred)
+    }
+
+    /// Returns whether `SO_PASSCRED` is on, see
+    /// [`set_passcred`](UnixStream::set_passcred).
+    pub fn passcred(&self) -> io::Result<bool> {
+        crate::ucred::passcred(&self.inner)
+    }
+
+    /// Sends `buf` with the process ID and effective user and group IDs of
+    /// this process attached, which the kernel verifies.
+    ///
+    /// Receivers with `SO_PASSCRED` on get credentials with every message
+    /// anyway; this just makes sending them explicit.
+    pub async fn send_with_creds(&self, buf: &[u8]) -> io::Result<usize> {
+        crate::ucred::send_with_creds(&self.inner, buf).await
+    }
+
+    /// Receives into `buf`, returning the number of bytes received together
+    /// with the credentials of their sender.
+    ///
+    /// The credentials are checked by the kernel, so they can be trusted.
+    /// They are `None` unless [`set_passcred`](UnixStream::set_passcred) was on
+    /// when the message was sent. File descriptors passed along are closed.
+    pub async fn recv_with_creds(&self, buf: &mut [u8]) -> io::Result<(usize, Option<UCred>)> {
+        crate::ucred::recv_with_creds(&self.inner, buf).await
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.inner.take_error()
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    ///
+    /// This function will cause all pending and future I/O calls on the
+    /// specified portions to immediately return with an appropriate value
+    /// (see the documentation of `Shutdown`).
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.inner.shutdown(how)
+    }
+
+    /// Polls to read into several buffers at once, filling them in order,
+    /// with a single system call.
+    ///
+    /// Up to 16 non-empty buffers are read into per call. `poll_read_buf`
+    /// from `AsyncRead` reads into a `BufMut` the same way.
+    pub fn poll_read_vectored(&self, cx: &mut Context<'_>, bufs: &mut [IoSliceMut<'_>]) -> Poll<io::Result<usize>> {
+        self.inner.poll_read_vectored(cx, bufs)
+    }
+
+    /// Polls to write from several buffers at once, in order, with a single
+    /// system call, so e.g. a frame header and its payload go out together
+    /// without being copied into one buffer first.
+    ///
+    /// Up to 16 non-empty buffers are written from per call, and fewer bytes
+    /// than they hold may be written. `poll_write_buf` from `AsyncWrite`
+    /// writes a `Buf`, such as a chain, the same way.
+    pub fn poll_write_vectored(&self, cx: &mut Context<'_>, bufs: &[IoSlice<'_>]) -> Poll<io::Result<usize>> {
+        self.inner.poll_write_vectored(cx, bufs)
+    }
+
+    /// Sends `buf` together with the file descriptors `fds`, which the peer
+    /// receives as new descriptors for the same open files or sockets.
+    ///
+    /// The descriptors arrive with the first byte sent, so `buf` must not be
+    /// empty. As with `write`, only part of `buf` may be sent; the rest
+    /// should follow with plain writes. At most 253 descriptors can be sent
+    /// at once.
+    #[cfg(not(target_os = "windows"))]
+    pub async fn send_with_fds(&self, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+        if buf.is_empty() && !fds.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "file descriptors can't be sent without any data"));
+        }
+        futures::future::poll_fn(|cx| {
+            self.inner.poll_write_with(cx, |stream| crate::sys::send_msg(stream, buf, fds))
+        }).await
+    }
+
+    /// Reads into `buf` like `read`, appending the file descriptors that
+    /// were sent along with the bytes read to `fds`.
+    ///
+    /// Received descriptors are close-on-exec. If the peer passed more than
+    /// could be received, they are all closed and this fails with
+    /// `ErrorKind::InvalidData`; the bytes they came with are lost too, so
+    /// the connection should be considered broken.
+    #[cfg(not(target_os = "windows"))]
+    pub async fn recv_with_fds(&self, buf: &mut [u8], fds: &mut Vec<OwnedFd>) -> io::Result<usize> {
+        let (n, _) = futures::future::poll_fn(|cx| {
+            self.inner.poll_read_with(cx, |stream| crate::sys::recv_msg(stream, buf, Some(&mut *fds)))
+        }).await?;
+        Ok(n)
+    }
+
+    /// Always fails with `ErrorKind::Unsupported`, as Windows can't pass
+    /// sockets or handles over Unix sockets.
+    #[cfg(target_os = "windows")]
+    pub async fn send_with_fds(&self, _buf: &[u8], _fds: &[RawSocket]) -> io::Result<usize> {
+        Err(fd_passing_unsupported())
+    }
+
+    /// Always fails with `ErrorKind::Unsupported`, as Windows can't pass
+    /// sockets or handles over Unix sockets.
+    #[cfg(target_os = "windows")]
+    pub async fn recv_with_fds(&self, _buf: &mut [u8], _fds: &mut Vec<OwnedSocket>) -> io::Result<usize> {
+        Err(fd_passing_unsupported())
+    }
+
+    /// Splits this stream into a read half and a write half borrowing it,
+    /// which can be used concurrently without any locking.
+    pub fn split(&mut self) -> (ReadHalf<'_>, WriteHalf<'_>) {
+        crate::split::split(self)
+    }
+
+    /// Splits this stream into a read half and a write half which can be
+    /// moved to separate tasks and used concurrently without any locking.
+    ///
+    /// Dropping the write half shuts down the write direction of the
+    /// stream. The halves can be put back together with
+    /// [`OwnedReadHalf::reunite`].
+    pub fn into_split(self) -> (OwnedReadHalf, OwnedWriteHalf) {
+        crate::split::into_split(self)
+    }
+}
+
+impl AsyncRead for UnixStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+        self.project().inner.poll_read(cx, buf)
+    }
+
+    fn poll_read_buf<B: BufMut>(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut B) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_read_buf(cx, buf)
+    }
+}
+
+impl AsyncWrite for UnixStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize, Error>> {
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.project().inner.poll_shutdown(cx)
+    }
+
+    fn poll_write_buf<B: Buf>(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut B) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write_buf(cx, buf)
+    }
+}
+
+impl AsyncRead for &UnixStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut &self.inner).poll_read(cx, buf)
+    }
+
+    fn poll_read_buf<B: BufMut>(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut B) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.inner).poll_read_buf(cx, buf)
+    }
+}
+
+impl AsyncWrite for &UnixStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize, Error>> {
+        Pin::new(&mut &self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Pin::new(&mut &self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Pin::new(&mut &self.inner).poll_shutdown(cx)
+    }
+
+    fn poll_write_buf<B: Buf>(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut B) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.inner).poll_write_buf(cx, buf)
+    }
+}
+
+/// A Unix datagram socket.
+///
+/// Windows has no datagram Unix sockets; there, every constructor fails with
+/// `ErrorKind::Unsupported`, so applications can fall back at runtime.
+#[derive(Debug)]
+pub struct UnixDatagram {
+    inner: crate::datagram::UnixDatagram,
+}
+
+impl UnixDatagram {
+    /// Creates a new socket bound to `bind_path`.
+    pub fn bind<P: AsRef<Path>>(bind_path: P) -> io::Result<Self> {
+        crate::datagram::UnixDatagram::bind(bind_path).map(|inner| UnixDatagram { inner })
+    }
+
+    /// Creates a new socket which is not bound to any address.
+    ///
+    /// It can still send to other sockets, but they can't reply.
+    pub fn unbound() -> io::Result<Self> {
+        crate::datagram::UnixDatagram::unbound().map(|inner| UnixDatagram { inner })
+    }
+
+    /// Creates an unnamed pair of connected sockets.
+    pub fn pair() -> io::Result<(Self, Self)> {
+        let (a, b) = crate::datagram::UnixDatagram::pair()?;
+        Ok((UnixDatagram { inner: a }, UnixDatagram { inner: b }))
+    }
+
+    /// Creates a new socket from a `UnixDatagram` in the standard library,
+    /// moving it into nonblocking mode.
+    #[cfg(not(target_os = "windows"))]
+    pub fn from_std(socket: std::os::unix::net::UnixDatagram) -> io::Result<Self> {
+        crate::datagram::UnixDatagram::from_std(socket).map(|inner| UnixDatagram { inner })
+    }
+
+    /// Consumes this socket, returning the `UnixDatagram` from the standard
+    /// library it wraps, still in nonblocking mode.
+    #[cfg(not(target_os = "windows"))]
+    pub fn into_std(self) -> io::Result<std::os::unix::net::UnixDatagram> {
+        self.inner.into_std()
+    }
+
+    /// Connects this socket to the socket at `path`.
+    ///
+    /// [`send`](UnixDatagram::send) then sends there, and only datagrams
+    /// from that socket are received.
+    pub fn connect<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.inner.connect(path)
+    }
+
+    /// Sends `buf` as one datagram to the connected peer.
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        futures::future::poll_fn(|cx| self.inner.poll_send(cx, buf)).await
+    }
+
+    /// Receives one datagram from the connected peer into `buf`.
+    ///
+    /// If the datagram doesn't fit, the rest of it is discarded.
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        futures::future::poll_fn(|cx| self.inner.poll_recv(cx, buf)).await
+    }
+
+    /// Sends `buf` as one datagram to the socket at `target`.
+    pub async fn send_to<P: AsRef<Path>>(&self, buf: &[u8], target: P) -> io::Result<usize> {
+        let target = target.as_ref();
+        futures::future::poll_fn(|cx| self.inner.poll_send_to(cx, buf, target)).await
+    }
+
+    /// Sends `buf` as one datagram to the socket named `name` in the abstract
+    /// namespace, see [`UnixListener::bind_abstract`].
+    ///
+    /// Only Linux and Android have an abstract namespace; elsewhere this
+    /// fails with `ErrorKind::Unsupported`.
+    pub async fn send_to_abstract<N: AsRef<[u8]>>(&self, buf: &[u8], name: N) -> io::Result<usize> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        return {
+            let name = crate::sys::abstract_name(name.as_ref());
+            futures::future::poll_fn(|cx| {
+                self.inner.poll_write_with(cx, |socket| crate::sys::send_to(socket, buf, &name))
+            }).await
+        };
+
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            let _ = (buf, name);
+            Err(abstract_unsupported())
+        }
+    }
+
+    /// Receives one datagram into `buf`, together with the address of the
+    /// socket it came from.
+    ///
+    /// If the datagram doesn't fit, the rest of it is discarded.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let (n, addr) = futures::future::poll_fn(|cx| self.inner.poll_recv_from(cx, buf)).await?;
+        Ok((n, addr.into()))
+    }
+
+    /// Sends `buf` as one datagram to the connected peer, together with the
+    /// file descriptors `fds`, see [`UnixStream::send_with_fds`].
+    #[cfg(not(target_os = "windows"))]
+    pub async fn send_with_fds(&self, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+        futures::future::poll_fn(|cx| {
+            self.inner.poll_write_with(cx, |socket| crate::sys::send_msg(socket, buf, fds))
+        }).await
+    }
+
+    /// Receives one datagram into `buf`, appending the file descriptors sent
+    /// with it to `fds`, see [`UnixStream::recv_with_fds`].
+    ///
+    /// If the datagram doesn't fit, the rest of it is discarded, but its
+    /// descriptors are still received.
+    #[cfg(not(target_os = "windows"))]
+    pub async fn recv_with_fds(&self, buf: &mut [u8], fds: &mut Vec<OwnedFd>) -> io::Result<usize> {
+        let (n, _) = futures::future::poll_fn(|cx| {
+            self.inner.poll_read_with(cx, |socket| crate::sys::recv_msg(socket, buf, Some(&mut *fds)))
+        }).await?;
+        Ok(n)
+    }
+
+    /// Always fails with `ErrorKind::Unsupported`, as Windows can't pass
+    /// sockets or handles over Unix sockets.
+    #[cfg(target_os = "windows")]
+    pub async fn send_with_fds(&self, _buf: &[u8], _fds: &[RawSocket]) -> io::Result<usize> {
+        Err(fd_passing_unsupported())
+    }
+
+    /// Always fails with `ErrorKind::Unsupported`, as Windows can't pass
+    /// sockets or handles over Unix sockets.
+    #[cfg(target_os = "windows")]
+    pub async fn recv_with_fds(&self, _buf: &mut [u8], _fds: &mut Vec<OwnedSocket>) -> io::Result<usize> {
+        Err(fd_passing_unsupported())
+    }
+
+    /// Sets `SO_PASSCRED` for this socket, as
+    /// [`UnixStream::set_passcred`] does for streams.
+    pub fn set_passcred(&self, passcred: bool) -> io::Result<()> {
+        crate::ucred::set_passcred(&self.inner, passcred)
+    }
+
+    /// Returns whether `SO_PASSCRED` is on, see
+    /// [`UnixStream::set_passcred`].
+    pub fn passcred(&self) -> io::Result<bool> {
+        crate::ucred::passcred(&self.inner)
+    }
+
+    /// Sends `buf` with the credentials of this process attached, like
+    /// [`UnixStream::send_with_creds`].
+    pub async fn send_with_creds(&self, buf: &[u8]) -> io::Result<usize> {
+        crate::ucred::send_with_creds(&self.inner, buf).await
+    }
+
+    /// Receives a datagram into `buf` together with the credentials of its
+    /// sender, like [`UnixStream::recv_with_creds`].
+    pub async fn recv_with_creds(&self, buf: &mut [u8]) -> io::Result<(usize, Option<UCred>)> {
+        crate::ucred::recv_with_creds(&self.inner, buf).await
+    }
+
+    /// Returns the address this socket is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr().map(SocketAddr::from)
+    }
+
+    /// Returns the address of the socket this one is connected to.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr().map(SocketAddr::from)
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.inner.take_error()
+    }
+
+    /// Shuts down the read, write, or both halves of this socket.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.inner.shutdown(how)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod raw {
+    use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+
+    use super::{UnixDatagram, UnixListener, UnixStream};
+
+    impl AsRawFd for UnixDatagram {
+        fn as_raw_fd(&self) -> RawFd {
+            self.inner.as_raw_fd()
+        }
+    }
+
+    impl IntoRawFd for UnixDatagram {
+        /// # Panics
+        ///
+        /// Panics if the socket can't be deregistered from the reactor.
+        fn into_raw_fd(self) -> RawFd {
+            self.into_std().expect("failed to deregister the socket").into_raw_fd()
+        }
+    }
+
+    impl FromRawFd for UnixDatagram {
+        /// # Panics
+        ///
+        /// Panics if called outside of a tokio runtime.
+        unsafe fn from_raw_fd(fd: RawFd) -> Self {
+            UnixDatagram::from_std(std::os::unix::net::UnixDatagram::from_raw_fd(fd))
+                .expect("failed to register the socket with the reactor")
+        }
+    }
+
+    impl AsRawFd for UnixListener {
+        fn as_raw_fd(&self) -> RawFd {
+            self.inner.as_raw_fd()
+        }
+    }
+
+    impl IntoRawFd for UnixListener {
+        /// # Panics
+        ///
+        /// Panics if the listener can't be deregistered from the reactor.
+        fn into_raw_fd(self) -> RawFd {
+            self.into_std().expect("failed to deregister the listener").into_raw_fd()
+        }
+    }
+
+    impl FromRawFd for UnixListener {
+        /// # Panics
+        ///
+        /// Panics if called outside of a tokio runtime.
+        unsafe fn from_raw_fd(fd: RawFd) -> Self {
+            UnixListener::from_std(std::os::unix::net::UnixListener::from_raw_fd(fd))
+                .expect("failed to register the listener with the reactor")
+        }
+    }
+
+    impl AsRawFd for UnixStream {
+        fn as_raw_fd(&self) -> RawFd {
+            self.inner.as_raw_fd()
+        }
+    }
+
+    impl IntoRawFd for UnixStream {
+        /// # Panics
+        ///
+        /// Panics if the stream can't be deregistered from the reactor.
+        fn into_raw_fd(self) -> RawFd {
+            self.into_std().expect("failed to deregister the stream").into_raw_fd()
+        }
+    }
+
+    impl FromRawFd for UnixStream {
+        /// # Panics
+        ///
+        /// Panics if called outside of a tokio runtime.
+        unsafe fn from_raw_fd(fd: RawFd) -> Self {
+            UnixStream::from_std(std::os::unix::net::UnixStream::from_raw_fd(fd))
+                .expect("failed to register the stream with the reactor")
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod raw {
+    use std::os::windows::io::{AsRawSocket, RawSocket};
+
+    use super::{UnixListener, UnixStream};
+
+    impl AsRawSocket for UnixListener {
+        fn as_raw_socket(&self) -> RawSocket {
+            self.inner.as_raw_socket()
+        }
+    }
+
+    impl AsRawSocket for UnixStream {
+        fn as_raw_socket(&self) -> RawSocket {
+            self.inner.as_raw_socket()
+        }
+    }
+}
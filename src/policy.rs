@@ -0,0 +1,108 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::UCred;
+
+type CredPredicate = Arc<dyn Fn(&UCred) -> bool + Send + Sync>;
+type CredCallback = Arc<dyn Fn(&UCred) + Send + Sync>;
+
+/// Decides which peers a [`UnixListener`](crate::UnixListener) accepts
+/// connections from, based on their credentials.
+///
+/// A peer must pass every check that has been configured; a policy with no
+/// checks allows everyone. Rejected connections are closed during accept,
+/// before they are ever returned, and logged at the `warn` level.
+///
+/// Where peer credentials are unavailable, see
+/// [`UnixStream::peer_cred`](crate::UnixStream::peer_cred), every connection
+/// is rejected.
+#[derive(Clone, Default)]
+pub struct AccessPolicy {
+    uids: Option<Vec<u32>>,
+    gids: Option<Vec<u32>>,
+    pid: Option<Arc<dyn Fn(i32) -> bool + Send + Sync>>,
+    custom: Option<CredPredicate>,
+    on_reject: Option<CredCallback>,
+}
+
+impl AccessPolicy {
+    /// Creates a policy which allows everyone.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows peers whose user ID is `uid`.
+    ///
+    /// Once any user ID is allowed, peers running as any other user are
+    /// rejected.
+    pub fn allow_uid(mut self, uid: u32) -> Self {
+        self.uids.get_or_insert_with(Vec::new).push(uid);
+        self
+    }
+
+    /// Allows peers whose group ID is `gid`.
+    ///
+    /// Once any group ID is allowed, peers running as any other group are
+    /// rejected.
+    pub fn allow_gid(mut self, gid: u32) -> Self {
+        self.gids.get_or_insert_with(Vec::new).push(gid);
+        self
+    }
+
+    /// Only allows peers whose process ID satisfies `predicate`.
+    ///
+    /// Peers whose process ID the platform doesn't report are rejected.
+    pub fn pid<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(i32) -> bool + Send + Sync + 'static,
+    {
+        self.pid = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Only allows peers whose credentials satisfy `predicate`.
+    pub fn custom<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&UCred) -> bool + Send + Sync + 'static,
+    {
+        self.custom = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Calls `callback` with the credentials of every rejected peer.
+    pub fn on_reject<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&UCred) + Send + Sync + 'static,
+    {
+        self.on_reject = Some(Arc::new(callback));
+        self
+    }
+
+    /// Returns `true` if a peer with credentials `cred` is allowed.
+    pub fn allows(&self, cred: &UCred) -> bool {
+        self.uids.as_ref().map_or(true, |uids| uids.contains(&cred.uid()))
+            && self.gids.as_ref().map_or(true, |gids| gids.contains(&cred.gid()))
+            && self.pid.as_ref().map_or(true, |pid| cred.pid().is_some_and(|p| pid(p)))
+            && self.custom.as_ref().map_or(true, |custom| custom(cred))
+    }
+
+    /// Reports that a peer with credentials `cred` has been turned away.
+    pub(crate) fn reject(&self, cred: &UCred) {
+        log::warn!("rejected connection from uid {}, gid {}, pid {:?}", cred.uid(), cred.gid(), cred.pid());
+        if let Some(on_reject) = self.on_reject.as_ref() {
+            on_reject(cred);
+        }
+    }
+}
+
+impl fmt::Debug for AccessPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AccessPolicy")
+            .field("uids", &self.uids)
+            .field("gids", &self.gids)
+            .field("pid", &self.pid.is_some())
+            .field("custom", &self.custom.is_some())
+            .field("on_reject", &self.on_reject.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,68 @@
+//! Running socket calls once a `PollEvented` is ready for them.
+
+use std::io;
+use std::os::unix::io::AsRawFd;
+
+use futures::task::{Context, Poll};
+use mio::{Evented, Ready};
+use tokio::io::PollEvented;
+
+/// Once `io` is readable, runs `f` on it, registering the current task to be
+/// woken and retrying later if `f` would block.
+pub(crate) fn poll_read_with<E, T, F>(io: &PollEvented<E>, cx: &mut Context<'_>, f: F) -> Poll<io::Result<T>>
+where
+    E: Evented,
+    F: FnOnce(&E) -> io::Result<T>,
+{
+    futures::ready!(io.poll_read_ready(cx, Ready::readable()))?;
+
+    match f(io.get_ref()) {
+        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+            io.clear_read_ready(cx, Ready::readable())?;
+            Poll::Pending
+        }
+        r => Poll::Ready(r),
+    }
+}
+
+/// Once `io` is writable, runs `f` on it, registering the current task to be
+/// woken and retrying later if `f` would block.
+pub(crate) fn poll_write_with<E, T, F>(io: &PollEvented<E>, cx: &mut Context<'_>, f: F) -> Poll<io::Result<T>>
+where
+    E: Evented,
+    F: FnOnce(&E) -> io::Result<T>,
+{
+    futures::ready!(io.poll_write_ready(cx))?;
+
+    match f(io.get_ref()) {
+        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+            io.clear_write_ready(cx)?;
+            Poll::Pending
+        }
+        r => Poll::Ready(r),
+    }
+}
+
+/// A socket registered with the reactor, so calls can be made on it once it
+/// is ready for them.
+pub(crate) trait PollSocket {
+    type Socket: Evented + AsRawFd;
+
+    fn poll_evented(&self) -> &PollEvented<Self::Socket>;
+
+    /// Runs `f` on the socket once it is readable; see [`poll_read_with`].
+    fn poll_read_with<T, F>(&self, cx: &mut Context<'_>, f: F) -> Poll<io::Result<T>>
+    where
+        F: FnOnce(&Self::Socket) -> io::Result<T>,
+    {
+        poll_read_with(self.poll_evented(), cx, f)
+    }
+
+    /// Runs `f` on the socket once it is writable; see [`poll_write_with`].
+    fn poll_write_with<T, F>(&self, cx: &mut Context<'_>, f: F) -> Poll<io::Result<T>>
+    where
+        F: FnOnce(&Self::Socket) -> io::Result<T>,
+    {
+        poll_write_with(self.poll_evented(), cx, f)
+    }
+}
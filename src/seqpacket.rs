@@ -0,0 +1,295 @@
+//! `SOCK_SEQPACKET` sockets, which are connected like streams but keep the
+//! boundaries between messages like datagrams.
+
+use std::fmt;
+use std::io;
+use std::mem::ManuallyDrop;
+use std::net::Shutdown;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::net;
+use std::path::Path;
+use std::pin::Pin;
+
+use futures::task::{Context, Poll};
+use futures::Stream;
+use mio::unix::EventedFd;
+use mio::{Evented, PollOpt, Ready, Token};
+use tokio::io::PollEvented;
+
+use crate::ready::{poll_read_with, poll_write_with};
+use crate::socket_file::SocketFile;
+use crate::{SocketAddr, UCred};
+
+/// A seqpacket socket which can accept connections from other seqpacket
+/// sockets.
+///
+/// Binds and accepts like a [`UnixListener`](crate::UnixListener), but the
+/// connections are [`UnixSeqpacket`]s. Not every platform supports
+/// `SOCK_SEQPACKET` for Unix sockets; where it is missing, binding fails.
+pub struct UnixSeqpacketListener {
+    io: PollEvented<Socket>,
+    file: Option<SocketFile>,
+}
+
+impl UnixSeqpacketListener {
+    /// Creates a new listener bound to `bind_path`.
+    pub fn bind<P: AsRef<Path>>(bind_path: P) -> io::Result<Self> {
+        let path = bind_path.as_ref();
+        let listener = Self::bind_addr(path.as_os_str().as_bytes())?;
+        Ok(UnixSeqpacketListener { file: SocketFile::new(path).ok(), ..listener })
+    }
+
+    /// Creates a new listener bound to `name` in the abstract namespace.
+    ///
+    /// `name` is given without the leading NUL byte. Only Linux and Android
+    /// have an abstract namespace; elsewhere this fails with
+    /// `ErrorKind::Unsupported`.
+    pub fn bind_abstract<N: AsRef<[u8]>>(name: N) -> io::Result<Self> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        return Self::bind_addr(&crate::sys::abstract_name(name.as_ref()));
+
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            let _ = name;
+            Err(crate::merge::abstract_unsupported())
+        }
+    }
+
+    fn bind_addr(addr: &[u8]) -> io::Result<Self> {
+        let fd = crate::sys::socket(libc::SOCK_SEQPACKET)?;
+        crate::sys::bind(&fd, addr)?;
+        crate::sys::listen(&fd, crate::builder::DEFAULT_BACKLOG)?;
+        crate::sys::set_nonblocking(&fd)?;
+        Ok(UnixSeqpacketListener { io: PollEvented::new(Socket(fd))?, file: None })
+    }
+
+    /// Sets whether the socket file this listener was bound to is removed
+    /// when the listener is dropped. Off by default.
+    ///
+    /// As with [`UnixListener::set_remove_on_drop`](crate::UnixListener::set_remove_on_drop),
+    /// the file is only removed if it is still the one this listener
+    /// created.
+    pub fn set_remove_on_drop(&mut self, remove: bool) {
+        if let Some(file) = self.file.as_mut() {
+            file.set_remove_on_drop(remove);
+        }
+    }
+
+    /// Returns the local socket address of this listener.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().with_std(net::UnixStream::local_addr).map(SocketAddr::from)
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.io.get_ref().with_std(net::UnixStream::take_error)
+    }
+
+    /// Accepts a new incoming connection to this listener.
+    ///
+    /// Resolves to the connected socket together with the address of its
+    /// peer.
+    pub async fn accept(&mut self) -> io::Result<(UnixSeqpacket, SocketAddr)> {
+        futures::future::poll_fn(|cx| self.poll_accept(cx)).await
+    }
+
+    /// Polls to accept a new incoming connection to this listener.
+    ///
+    /// If no connection is pending, the current task is registered to be
+    /// woken once one arrives and `Poll::Pending` is returned.
+    pub fn poll_accept(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<(UnixSeqpacket, SocketAddr)>> {
+        let fd = futures::ready!(poll_read_with(&self.io, cx, |socket| crate::sys::accept(&socket.0)))?;
+        let socket = UnixSeqpacket::new(fd)?;
+        let addr = socket.peer_addr()?;
+        Poll::Ready(Ok((socket, addr)))
+    }
+}
+
+impl Stream for UnixSeqpacketListener {
+    type Item = io::Result<(UnixSeqpacket, SocketAddr)>;
+
+    /// Yields each accepted connection, or the error the accept failed with.
+    /// The stream never ends.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_accept(cx).map(Some)
+    }
+}
+
+impl fmt::Debug for UnixSeqpacketListener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UnixSeqpacketListener")
+            .field("fd", &self.as_raw_fd())
+            .field("local_addr", &self.local_addr().ok())
+            .finish()
+    }
+}
+
+impl AsRawFd for UnixSeqpacketListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().0.as_raw_fd()
+    }
+}
+
+/// A connected seqpacket socket.
+///
+/// Every [`send`](UnixSeqpacket::send) delivers one message, and every
+/// [`recv`](UnixSeqpacket::recv) receives exactly one, so the peer sees the
+/// same boundaries that were sent.
+pub struct UnixSeqpacket {
+    io: PollEvented<Socket>,
+}
+
+impl UnixSeqpacket {
+    fn new(fd: OwnedFd) -> io::Result<Self> {
+        Ok(UnixSeqpacket { io: PollEvented::new(Socket(fd))? })
+    }
+
+    /// Connects to the seqpacket socket at `path`.
+    pub async fn connect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::connect_addr(path.as_ref().as_os_str().as_bytes()).await
+    }
+
+    /// Connects to the seqpacket socket named `name` in the abstract
+    /// namespace.
+    ///
+    /// `name` is given without the leading NUL byte. Only Linux and Android
+    /// have an abstract namespace; elsewhere this fails with
+    /// `ErrorKind::Unsupported`.
+    pub async fn connect_abstract<N: AsRef<[u8]>>(name: N) -> io::Result<Self> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        return Self::connect_addr(&crate::sys::abstract_name(name.as_ref())).await;
+
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            let _ = name;
+            Err(crate::merge::abstract_unsupported())
+        }
+    }
+
+    async fn connect_addr(addr: &[u8]) -> io::Result<Self> {
+        let fd = crate::sys::socket(libc::SOCK_SEQPACKET)?;
+        crate::sys::set_nonblocking(&fd)?;
+        let in_progress = match crate::sys::connect(&fd, addr) {
+            Ok(()) => false,
+            Err(ref err) if err.raw_os_error() == Some(libc::EINPROGRESS) => true,
+            Err(err) => return Err(err),
+        };
+
+        let socket = Self::new(fd)?;
+        if in_progress {
+            futures::future::poll_fn(|cx| socket.io.poll_write_ready(cx)).await?;
+            if let Some(err) = socket.take_error()? {
+                return Err(err);
+            }
+        }
+        Ok(socket)
+    }
+
+    /// Creates an unnamed pair of connected sockets.
+    pub fn pair() -> io::Result<(Self, Self)> {
+        let (a, b) = crate::sys::socketpair(libc::SOCK_SEQPACKET)?;
+        crate::sys::set_nonblocking(&a)?;
+        crate::sys::set_nonblocking(&b)?;
+        Ok((Self::new(a)?, Self::new(b)?))
+    }
+
+    /// Sends `buf` as one message.
+    ///
+    /// The message is sent whole or not at all; one larger than the send
+    /// buffer fails with `EMSGSIZE`.
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        futures::future::poll_fn(|cx| self.poll_send(cx, buf)).await
+    }
+
+    /// Receives one message into `buf`.
+    ///
+    /// Resolves to the number of bytes received and whether the message was
+    /// truncated because it didn't fit into `buf`; the rest of a truncated
+    /// message is discarded. A message of zero bytes with `false` means the
+    /// peer has shut down the connection, unless it sent an empty message.
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        futures::future::poll_fn(|cx| self.poll_recv(cx, buf)).await
+    }
+
+    /// Polls to send `buf` as one message, see [`send`](UnixSeqpacket::send).
+    pub fn poll_send(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        poll_write_with(&self.io, cx, |socket| crate::sys::send(&socket.0, buf))
+    }
+
+    /// Polls to receive one message into `buf`, see
+    /// [`recv`](UnixSeqpacket::recv).
+    pub fn poll_recv(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<(usize, bool)>> {
+        poll_read_with(&self.io, cx, |socket| crate::sys::recv_msg(&socket.0, buf, None))
+            .map_ok(|(n, flags)| (n, flags & libc::MSG_TRUNC != 0))
+    }
+
+    /// Returns the socket address of the local half of this connection.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().with_std(net::UnixStream::local_addr).map(SocketAddr::from)
+    }
+
+    /// Returns the socket address of the remote half of this connection.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().with_std(net::UnixStream::peer_addr).map(SocketAddr::from)
+    }
+
+    /// Returns the credentials of the process on the other end of this
+    /// connection, see [`UnixStream::peer_cred`](crate::UnixStream::peer_cred).
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        crate::ucred::peer_cred(self)
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.io.get_ref().with_std(net::UnixStream::take_error)
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.io.get_ref().with_std(|socket| socket.shutdown(how))
+    }
+}
+
+impl fmt::Debug for UnixSeqpacket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UnixSeqpacket")
+            .field("fd", &self.as_raw_fd())
+            .field("local_addr", &self.local_addr().ok())
+            .field("peer_addr", &self.peer_addr().ok())
+            .finish()
+    }
+}
+
+impl AsRawFd for UnixSeqpacket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().0.as_raw_fd()
+    }
+}
+
+/// A socket mio knows nothing about beyond its descriptor.
+#[derive(Debug)]
+struct Socket(OwnedFd);
+
+impl Socket {
+    /// Runs `f` on the socket as a std `UnixStream`, whose address, error
+    /// and shutdown calls work for Unix sockets of any type.
+    fn with_std<T>(&self, f: impl FnOnce(&net::UnixStream) -> T) -> T {
+        let socket = ManuallyDrop::new(unsafe { net::UnixStream::from_raw_fd(self.0.as_raw_fd()) });
+        f(&socket)
+    }
+}
+
+impl Evented for Socket {
+    fn register(&self, poll: &mio::Poll, token: Token, interest: Ready, opts: PollOpt) -> io::Result<()> {
+        EventedFd(&self.0.as_raw_fd()).register(poll, token, interest, opts)
+    }
+
+    fn reregister(&self, poll: &mio::Poll, token: Token, interest: Ready, opts: PollOpt) -> io::Result<()> {
+        EventedFd(&self.0.as_raw_fd()).reregister(poll, token, interest, opts)
+    }
+
+    fn deregister(&self, poll: &mio::Poll) -> io::Result<()> {
+        EventedFd(&self.0.as_raw_fd()).deregister(poll)
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future::{AbortHandle, Abortable};
+use futures::task::{AtomicWaker, Context, Poll, Waker};
+use tokio::task::JoinHandle;
+
+/// Handle for shutting down a server gracefully.
+///
+/// Obtained from [`UnixListener::shutdown_handle`](crate::UnixListener::shutdown_handle).
+/// Once [`shutdown`](ShutdownHandle::shutdown) is called, the listener's
+/// `Stream` and `Incoming` end, and the connection tasks started through
+/// [`spawn`](ShutdownHandle::spawn) get a deadline to finish before they are
+/// aborted.
+#[derive(Clone, Debug)]
+pub struct ShutdownHandle {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    triggered: AtomicBool,
+    listener: AtomicWaker,
+    tasks: Mutex<Tasks>,
+}
+
+#[derive(Debug, Default)]
+struct Tasks {
+    next_id: u64,
+    running: HashMap<u64, AbortHandle>,
+    /// Set once the shutdown deadline has passed.
+    aborting: bool,
+    drained: Vec<Waker>,
+}
+
+impl ShutdownHandle {
+    pub(crate) fn new() -> Self {
+        ShutdownHandle {
+            inner: Arc::new(Inner {
+                triggered: AtomicBool::new(false),
+                listener: AtomicWaker::new(),
+                tasks: Mutex::new(Tasks::default()),
+            })
+        }
+    }
+
+    /// Spawns `task` onto the tokio runtime and tracks it until it completes.
+    ///
+    /// The returned handle resolves to `None` if the task was aborted because
+    /// it did not finish before the shutdown deadline. Tasks spawned after
+    /// the deadline are aborted straight away.
+    pub fn spawn<F>(&self, task: F) -> JoinHandle<Option<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (abort, registration) = AbortHandle::new_pair();
+        let guard = {
+            let mut tasks = self.inner.tasks.lock().unwrap();
+            let id = tasks.next_id;
+            tasks.next_id += 1;
+            if tasks.aborting {
+                abort.abort();
+            }
+            tasks.running.insert(id, abort);
+            TaskGuard { inner: self.inner.clone(), id }
+        };
+
+        tokio::spawn(async move {
+            let _guard = guard;
+            Abortable::new(task, registration).await.ok()
+        })
+    }
+
+    /// Returns the number of tracked tasks which are still running.
+    pub fn active_tasks(&self) -> usize {
+        self.inner.tasks.lock().unwrap().running.len()
+    }
+
+    /// Returns `true` once shutdown has been initiated.
+    pub fn is_shutting_down(&self) -> bool {
+        self.inner.triggered.load(Ordering::Acquire)
+    }
+
+    /// Stops accepting connections and waits up to `timeout` for the tracked
+    /// tasks to finish, then aborts whichever are left.
+    ///
+    /// Resolves once every tracked task is gone, to the number of tasks that
+    /// had to be aborted.
+    pub async fn shutdown(&self, timeout: Duration) -> usize {
+        self.inner.triggered.store(true, Ordering::Release);
+        self.inner.listener.wake();
+
+        if tokio::time::timeout(timeout, self.drained()).await.is_ok() {
+            return 0;
+        }
+
+        let aborted = {
+            let mut tasks = self.inner.tasks.lock().unwrap();
+            tasks.aborting = true;
+            tasks.running.values().for_each(AbortHandle::abort);
+            tasks.running.len()
+        };
+        log::debug!("aborting {} connection tasks still running after {:?}", aborted, timeout);
+
+        self.drained().await;
+        aborted
+    }
+
+    /// Polled by the listener; ready once shutdown has been initiated.
+    pub(crate) fn poll_triggered(&self, cx: &mut Context<'_>) -> Poll<()> {
+        self.inner.listener.register(cx.waker());
+        if self.is_shutting_down() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    async fn drained(&self) {
+        futures::future::poll_fn(|cx| {
+            let mut tasks = self.inner.tasks.lock().unwrap();
+            if tasks.running.is_empty() {
+                Poll::Ready(())
+            } else {
+                if !tasks.drained.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    tasks.drained.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }).await
+    }
+}
+
+/// Untracks a task once it completes or is aborted.
+struct TaskGuard {
+    inner: Arc<Inner>,
+    id: u64,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        let mut tasks = self.inner.tasks.lock().unwrap();
+        tasks.running.remove(&self.id);
+        if tasks.running.is_empty() {
+            tasks.drained.drain(..).for_each(Waker::wake);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(target_os = "windows")]
+use mio_uds_windows::net::UnixStream as StdUnixStream;
+
+/// The filesystem entry a listener created by binding to a path.
+///
+/// Remembers which file was created, so that it is never confused with a
+/// socket file another listener has since bound at the same path.
+#[derive(Debug)]
+pub(crate) struct SocketFile {
+    path: PathBuf,
+    id: FileId,
+    remove_on_drop: bool
+}
+
+impl SocketFile {
+    /// Records the socket file a listener has just been bound to at `path`.
+    pub(crate) fn new(path: &Path) -> io::Result<SocketFile> {
+        // Relative paths are resolved now, so a later change of the working
+        // directory can't make us remove some other file.
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            env::current_dir()?.join(path)
+        };
+        let id = file_id(&fs::symlink_metadata(&path)?);
+
+        Ok(SocketFile { path, id, remove_on_drop: false })
+    }
+
+    /// Sets whether the file is removed when this `SocketFile` is dropped.
+    pub(crate) fn set_remove_on_drop(&mut self, remove: bool) {
+        self.remove_on_drop = remove;
+    }
+
+    /// Removes the file, unless it has been replaced since it was recorded.
+    pub(crate) fn remove(mut self) -> io::Result<()> {
+        self.remove_on_drop = false;
+        self.remove_if_unchanged()
+    }
+
+    fn remove_if_unchanged(&self) -> io::Result<()> {
+        match fs::symlink_metadata(&self.path) {
+            Ok(ref meta) if file_id(meta) == self.id => fs::remove_file(&self.path),
+            Ok(_) => {
+                log::debug!("not removing {}: it was replaced by another file", self.path.display());
+                Ok(())
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err)
+        }
+    }
+}
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        if self.remove_on_drop {
+            if let Err(err) = self.remove_if_unchanged() {
+                log::warn!("failed to remove socket file {}: {}", self.path.display(), err);
+            }
+        }
+    }
+}
+
+/// Removes the socket file at `path` if nothing is listening on it anymore.
+///
+/// A missing `path` is not an error. The file is probed with a connection
+/// attempt, and only removed if that attempt is refused. Fails with
+/// `AddrInUse` if a listener accepted the probe, and with `AlreadyExists` if
+/// `path` is not a socket at all; neither file is touched.
+pub(crate) fn remove_stale(path: &Path) -> io::Result<()> {
+    remove_socket_file(path, true)
+}
+
+/// Removes the socket file at `path`, whether or not a listener is still
+/// accepting on it.
+///
+/// A missing `path` is not an error, but anything other than a socket is
+/// left alone and reported with `AlreadyExists`.
+pub(crate) fn remove_any(path: &Path) -> io::Result<()> {
+    remove_socket_file(path, false)
+}
+
+fn remove_socket_file(path: &Path, only_stale: bool) -> io::Result<()> {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err)
+    };
+
+    if !is_socket(&meta) {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists,
+                                  format!("{} exists and is not a socket", path.display())));
+    }
+
+    if only_stale {
+        if is_live(path)? {
+            return Err(io::Error::new(io::ErrorKind::AddrInUse,
+                                      format!("a listener is still accepting on {}", path.display())));
+        }
+        log::debug!("removing stale socket file {}", path.display());
+    }
+
+    match fs::remove_file(path) {
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        res => res
+    }
+}
+
+/// Probes the socket file at `path` with a connection attempt, returning
+/// `true` unless it is refused.
+///
+/// The attempt doesn't block: a full backlog would otherwise hold it up for
+/// as long as the listener is busy, and it fails with `EAGAIN` instead, which
+/// shows a listener is there just as well.
+#[cfg(not(target_os = "windows"))]
+fn is_live(path: &Path) -> io::Result<bool> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let fd = crate::sys::socket(libc::SOCK_STREAM)?;
+    crate::sys::set_nonblocking(&fd)?;
+    match crate::sys::connect(&fd, path.as_os_str().as_bytes()) {
+        Ok(()) => Ok(true),
+        Err(ref err) if err.kind() == io::ErrorKind::ConnectionRefused => Ok(false),
+        Err(ref err) if matches!(err.raw_os_error(), Some(libc::EAGAIN) | Some(libc::EINPROGRESS)) => Ok(true),
+        Err(err) => Err(err)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn is_live(path: &Path) -> io::Result<bool> {
+    match StdUnixStream::connect(path) {
+        Ok(_) => Ok(true),
+        Err(ref err) if err.kind() == io::ErrorKind::ConnectionRefused => Ok(false),
+        Err(err) => Err(err)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+type FileId = (u64, u64);
+
+#[cfg(not(target_os = "windows"))]
+fn file_id(meta: &fs::Metadata) -> FileId {
+    use std::os::unix::fs::MetadataExt;
+    (meta.dev(), meta.ino())
+}
+
+// The volume serial and file index are not available from a stable `std`
+// API, so the creation time is the closest thing to an inode we have.
+#[cfg(target_os = "windows")]
+type FileId = u64;
+
+#[cfg(target_os = "windows")]
+fn file_id(meta: &fs::Metadata) -> FileId {
+    use std::os::windows::fs::MetadataExt;
+    meta.creation_time()
+}
+
+#[cfg(not(target_os = "windows"))]
+fn is_socket(meta: &fs::Metadata) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    meta.file_type().is_socket()
+}
+
+#[cfg(target_os = "windows")]
+fn is_socket(meta: &fs::Metadata) -> bool {
+    // Windows has no socket file type; AF_UNIX socket files are reparse
+    // points that are not symbolic links.
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+    meta.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0 && !meta.file_type().is_symlink()
+}
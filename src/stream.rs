@@ -1,268 +1,387 @@
-use futures::Future;
-use iovec::IoVec;
-use mio::Ready;
-use mio_uds_windows as mio_uds;
-use mio_uds_windows::net::{self, SocketAddr};
-
-use std::fmt;
-use std::io;
-use std::net::Shutdown;
-use std::os::windows::io::{AsRawSocket, RawSocket};
-use std::path::Path;
-use tokio::io::{PollEvented, AsyncWrite, AsyncRead};
-use futures::task::{Poll, Context};
-use tokio::macros::support::Pin;
-use futures::io::Error;
-
-/// A structure representing a connected Unix socket.
-///
-/// This socket can be connected directly with `UnixStream::connect` or accepted
-/// from a listener with `UnixListener::incoming`. Additionally, a pair of
-/// anonymous Unix sockets can be created with `UnixStream::pair`.
-pub struct UnixStream {
-    io: PollEvented<mio_uds::UnixStream>,
-}
-
-/// Future returned by `UnixStream::connect` which will resolve to a
-/// `UnixStream` when the stream is connected.
-#[derive(Debug)]
-pub struct ConnectFuture {
-    inner: State,
-}
-
-#[derive(Debug)]
-enum State {
-    Waiting(UnixStream),
-    Error(io::Error),
-    Empty,
-}
-
-impl UnixStream {
-    /// Connects to the socket named by `path`.
-    ///
-    /// This function will create a new Unix socket and connect to the path
-    /// specified, associating the returned stream with the default event loop's
-    /// handle.
-    pub fn connect<P>(path: P) -> io::Result<ConnectFuture>
-    where
-        P: AsRef<Path>,
-    {
-        let res = mio_uds::UnixStream::connect(path)
-            .map(UnixStream::new);
-
-        let inner = match res {
-            Ok(stream) => State::Waiting(stream?),
-            Err(e) => State::Error(e),
-        };
-
-        Ok(ConnectFuture { inner })
-    }
-
-    /// Consumes a `UnixStream` in the standard library and returns a
-    /// nonblocking `UnixStream` from this crate.
-    ///
-    /// The returned stream will be associated with the given event loop
-    /// specified by `handle` and is ready to perform I/O.
-    pub fn from_std(stream: net::UnixStream) -> io::Result<UnixStream> {
-        let stream = mio_uds::UnixStream::from_stream(stream)?;
-        let io = PollEvented::new(stream)?;
-        Ok(UnixStream { io })
-    }
-
-    pub(crate) fn new(stream: mio_uds::UnixStream) -> io::Result<UnixStream> {
-        let io = PollEvented::new(stream)?;
-        Ok(UnixStream { io })
-    }
-
-    /// Test whether this socket is ready to be read or not.
-    pub fn poll_read_ready(&self, cx: &mut Context<'_>, ready: Ready) -> Poll<Result<Ready, io::Error>> {
-        self.io.poll_read_ready(cx,ready)
-    }
-
-    /// Test whether this socket is ready to be written to or not.
-    pub fn poll_write_ready(&self, cx: &mut Context<'_>) -> Poll<Result<Ready, io::Error>> {
-        self.io.poll_write_ready(cx)
-    }
-
-    /// Returns the socket address of the local half of this connection.
-    pub fn local_addr(&self) -> io::Result<SocketAddr> {
-        self.io.get_ref().local_addr()
-    }
-
-    /// Returns the socket address of the remote half of this connection.
-    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
-        self.io.get_ref().peer_addr()
-    }
-
-    /// Returns the value of the `SO_ERROR` option.
-    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
-        self.io.get_ref().take_error()
-    }
-
-    /// Shuts down the read, write, or both halves of this connection.
-    ///
-    /// This function will cause all pending and future I/O calls on the
-    /// specified portions to immediately return with an appropriate value
-    /// (see the documentation of `Shutdown`).
-    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
-        self.io.get_ref().shutdown(how)
-    }
-}
-
-impl AsyncRead for UnixStream {
-    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
-        <&UnixStream>::poll_read(Pin::new(&mut &*self), cx, buf)
-    }
-}
-
-impl AsyncWrite for UnixStream {
-    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
-        <&UnixStream>::poll_write(Pin::new(&mut &*self), cx, buf)
-    }
-
-    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        <&UnixStream>::poll_flush(Pin::new(&mut &*self), cx)
-    }
-
-    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
-        <&UnixStream>::poll_shutdown(Pin::new(&mut &*self), cx)
-    }
-}
-
-impl<'a> AsyncRead for &'a UnixStream {
-    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
-        if let Poll::Pending = <UnixStream>::poll_read_ready(*self, cx,Ready::readable())? {
-            return Poll::Pending;
-        }
-        let r =  {
-            // The `IoVec` type can't have a 0-length size, so we create a bunch
-            // of dummy versions on the stack with 1 length which we'll quickly
-            // overwrite.
-            let b1: &mut [u8] = &mut [0];
-            let b2: &mut [u8] = &mut [0];
-            let b3: &mut [u8] = &mut [0];
-            let b4: &mut [u8] = &mut [0];
-            let b5: &mut [u8] = &mut [0];
-            let b6: &mut [u8] = &mut [0];
-            let b7: &mut [u8] = &mut [0];
-            let b8: &mut [u8] = &mut [0];
-            let b9: &mut [u8] = &mut [0];
-            let b10: &mut [u8] = &mut [0];
-            let b11: &mut [u8] = &mut [0];
-            let b12: &mut [u8] = &mut [0];
-            let b13: &mut [u8] = &mut [0];
-            let b14: &mut [u8] = &mut [0];
-            let b15: &mut [u8] = &mut [0];
-            let b16: &mut [u8] = &mut [0];
-            let mut bufs: [&mut IoVec; 16] = [
-                b1.into(), b2.into(), b3.into(), b4.into(),
-                b5.into(), b6.into(), b7.into(), b8.into(),
-                b9.into(), b10.into(), b11.into(), b12.into(),
-                b13.into(), b14.into(), b15.into(), b16.into(),
-            ];
-
-            bufs[0] = IoVec::from_bytes_mut(buf).unwrap();
-            let n = 1;
-            self.io.get_ref().read_bufs(&mut bufs[..n])
-        };
-
-        match r {
-            Ok(n) => {
-                Poll::Ready(Ok(n))
-            }
-            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                self.io.clear_read_ready(cx,Ready::readable())?;
-                Poll::Pending
-            }
-            Err(e) => Poll::Ready(Err(e)),
-        }
-    }
-}
-
-impl<'a> AsyncWrite for &'a UnixStream {
-    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
-        if let Poll::Pending = <UnixStream>::poll_write_ready(*self, cx)? {
-            return Poll::Pending;
-        }
-        let r = {
-            // The `IoVec` type can't have a zero-length size, so create a dummy
-            // version from a 1-length slice which we'll overwrite with the
-            // `bytes_vec` method.
-            static DUMMY: &[u8] = &[0];
-            let iovec = <&IoVec>::from(DUMMY);
-            let mut bufs = [
-                iovec, iovec, iovec, iovec, iovec, iovec, iovec, iovec, iovec, iovec, iovec, iovec, iovec,
-                iovec, iovec, iovec,
-            ];
-
-            bufs[0] = IoVec::from_bytes(buf).unwrap();
-            let n = 1;
-            self.io.get_ref().write_bufs(&bufs[..n])
-        };
-
-        match r {
-            Ok(n) => {
-                Poll::Ready(Ok(n))
-            }
-            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                self.io.clear_write_ready(cx)?;
-                Poll::Pending
-            }
-            Err(e) => Poll::Ready(Err(e)),
-        }
-    }
-
-    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        Poll::Ready(Ok(()))
-    }
-
-    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
-        Poll::Ready(self.shutdown(Shutdown::Write))
-    }
-}
-
-impl fmt::Debug for UnixStream {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.io.get_ref().fmt(f)
-    }
-}
-
-impl AsRawSocket for UnixStream {
-    fn as_raw_socket(&self) -> RawSocket {
-        self.io.get_ref().as_raw_socket()
-    }
-}
-
-impl Future for ConnectFuture {
-    type Output = io::Result<UnixStream>;
-
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        use std::mem;
-
-        match self.inner {
-            State::Waiting(ref mut stream) => {
-                if let Poll::Pending = stream.io.poll_write_ready(cx)? {
-                    return Poll::Pending
-                }
-
-                if let Some(e) = stream.io.get_ref().take_error()? {
-                    return Poll::Ready(Err(e))
-                }
-            }
-            State::Error(_) => {
-                let e = match mem::replace(&mut self.inner, State::Empty) {
-                    State::Error(e) => e,
-                    _ => unreachable!(),
-                };
-
-                return Poll::Ready(Err(e))
-            },
-            State::Empty => panic!("can't poll stream twice"),
-        }
-
-        match mem::replace(&mut self.inner, State::Empty) {
-            State::Waiting(stream) => Poll::Ready(Ok(stream)),
-            _ => unreachable!(),
-        }
-    }
-}
+use futures::Future;
+use iovec::IoVec;
+use mio::Ready;
+#[cfg(target_os = "windows")]
+use mio_uds_windows as mio_uds;
+#[cfg(target_os = "windows")]
+use mio_uds_windows::net::{self, SocketAddr};
+#[cfg(not(target_os = "windows"))]
+use std::os::unix::net::{self, SocketAddr};
+
+use std::fmt;
+use std::io;
+use std::net::Shutdown;
+#[cfg(target_os = "windows")]
+use std::os::windows::io::{AsRawSocket, RawSocket};
+#[cfg(not(target_os = "windows"))]
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::path::Path;
+use tokio::io::{PollEvented, AsyncWrite, AsyncRead};
+use futures::task::{Poll, Context};
+use tokio::macros::support::Pin;
+use futures::io::Error;
+use bytes::{Buf, BufMut};
+use std::io::{IoSlice, IoSliceMut};
+
+/// The most buffers handed to a single vectored read or write.
+const MAX_BUFS: usize = 16;
+
+/// A structure representing a connected Unix socket.
+///
+/// This socket can be connected directly with `UnixStream::connect` or accepted
+/// from a listener with `UnixListener::incoming`. Additionally, a pair of
+/// anonymous Unix sockets can be created with `UnixStream::pair`.
+pub struct UnixStream {
+    io: PollEvented<mio_uds::UnixStream>,
+}
+
+/// Future returned by `UnixStream::connect` which will resolve to a
+/// `UnixStream` when the stream is connected.
+#[derive(Debug)]
+pub struct ConnectFuture {
+    inner: State,
+}
+
+#[derive(Debug)]
+enum State {
+    Waiting(UnixStream),
+    Error(io::Error),
+    Empty,
+}
+
+impl UnixStream {
+    /// Connects to the socket named by `path`.
+    ///
+    /// This function will create a new Unix socket and connect to the path
+    /// specified, associating the returned stream with the default event loop's
+    /// handle.
+    pub fn connect<P>(path: P) -> io::Result<ConnectFuture>
+    where
+        P: AsRef<Path>,
+    {
+        let res = mio_uds::UnixStream::connect(path)
+            .map(UnixStream::new);
+
+        let inner = match res {
+            Ok(stream) => State::Waiting(stream?),
+            Err(e) => State::Error(e),
+        };
+
+        Ok(ConnectFuture { inner })
+    }
+
+    /// Consumes a `UnixStream` in the standard library and returns a
+    /// nonblocking `UnixStream` from this crate.
+    ///
+    /// The returned stream will be associated with the given event loop
+    /// specified by `handle` and is ready to perform I/O.
+    pub fn from_std(stream: net::UnixStream) -> io::Result<UnixStream> {
+        let stream = mio_uds::UnixStream::from_stream(stream)?;
+        let io = PollEvented::new(stream)?;
+        Ok(UnixStream { io })
+    }
+
+    /// Consumes this stream, returning the `UnixStream` from the standard
+    /// library it wraps.
+    ///
+    /// The returned stream is still in nonblocking mode.
+    #[cfg(not(target_os = "windows"))]
+    pub fn into_std(self) -> io::Result<net::UnixStream> {
+        let stream = self.io.into_inner()?;
+        Ok(unsafe { net::UnixStream::from_raw_fd(stream.into_raw_fd()) })
+    }
+
+    /// Creates an unnamed pair of connected sockets.
+    #[cfg(not(target_os = "windows"))]
+    pub fn pair() -> io::Result<(UnixStream, UnixStream)> {
+        let (a, b) = mio_uds::UnixStream::pair()?;
+        Ok((UnixStream::new(a)?, UnixStream::new(b)?))
+    }
+
+    /// Creates an unnamed pair of connected sockets.
+    ///
+    /// Windows has no `socketpair`, so the pair is connected through a
+    /// listener bound to a temporary path, which is removed right away.
+    #[cfg(target_os = "windows")]
+    pub fn pair() -> io::Result<(UnixStream, UnixStream)> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!("tokio-uds-pair-{}-{}.sock", std::process::id(),
+                                                     NEXT_ID.fetch_add(1, Ordering::Relaxed)));
+
+        let listener = net::UnixListener::bind(&path)?;
+        let res = net::UnixStream::connect(&path)
+            .and_then(|a| listener.accept().map(|(b, _)| (a, b)));
+        let _ = std::fs::remove_file(&path);
+
+        let (a, b) = res?;
+        Ok((UnixStream::from_std(a)?, UnixStream::from_std(b)?))
+    }
+
+    /// Reads into several buffers at once, filling them in order, with a
+    /// single `readv` call.
+    ///
+    /// Only the first 16 non-empty buffers are used.
+    pub fn poll_read_vectored(&self, cx: &mut Context<'_>, bufs: &mut [IoSliceMut<'_>]) -> Poll<io::Result<usize>> {
+        futures::ready!(self.poll_read_ready(cx, Ready::readable()))?;
+
+        let r = {
+            // The `IoVec` type can't have a 0-length size, so we create a bunch
+            // of dummy versions on the stack with 1 length which we'll quickly
+            // overwrite.
+            let b1: &mut [u8] = &mut [0];
+            let b2: &mut [u8] = &mut [0];
+            let b3: &mut [u8] = &mut [0];
+            let b4: &mut [u8] = &mut [0];
+            let b5: &mut [u8] = &mut [0];
+            let b6: &mut [u8] = &mut [0];
+            let b7: &mut [u8] = &mut [0];
+            let b8: &mut [u8] = &mut [0];
+            let b9: &mut [u8] = &mut [0];
+            let b10: &mut [u8] = &mut [0];
+            let b11: &mut [u8] = &mut [0];
+            let b12: &mut [u8] = &mut [0];
+            let b13: &mut [u8] = &mut [0];
+            let b14: &mut [u8] = &mut [0];
+            let b15: &mut [u8] = &mut [0];
+            let b16: &mut [u8] = &mut [0];
+            let mut vecs: [&mut IoVec; MAX_BUFS] = [
+                b1.into(), b2.into(), b3.into(), b4.into(),
+                b5.into(), b6.into(), b7.into(), b8.into(),
+                b9.into(), b10.into(), b11.into(), b12.into(),
+                b13.into(), b14.into(), b15.into(), b16.into(),
+            ];
+
+            let mut n = 0;
+            for buf in bufs.iter_mut().filter(|buf| !buf.is_empty()).take(MAX_BUFS) {
+                vecs[n] = (&mut **buf).into();
+                n += 1;
+            }
+            if n == 0 {
+                return Poll::Ready(Ok(0));
+            }
+            self.io.get_ref().read_bufs(&mut vecs[..n])
+        };
+
+        match r {
+            Ok(n) => Poll::Ready(Ok(n)),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_read_ready(cx, Ready::readable())?;
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+
+    /// Writes from several buffers at once, in order, with a single
+    /// `writev` call.
+    ///
+    /// Only the first 16 non-empty buffers are used.
+    pub fn poll_write_vectored(&self, cx: &mut Context<'_>, bufs: &[IoSlice<'_>]) -> Poll<io::Result<usize>> {
+        futures::ready!(self.poll_write_ready(cx))?;
+
+        let r = {
+            // The `IoVec` type can't have a zero-length size, so create a dummy
+            // version from a 1-length slice which we'll overwrite with the
+            // buffers to write.
+            static DUMMY: &[u8] = &[0];
+            let mut vecs = [<&IoVec>::from(DUMMY); MAX_BUFS];
+
+            let mut n = 0;
+            for buf in bufs.iter().filter(|buf| !buf.is_empty()).take(MAX_BUFS) {
+                vecs[n] = (&**buf).into();
+                n += 1;
+            }
+            if n == 0 {
+                return Poll::Ready(Ok(0));
+            }
+            self.io.get_ref().write_bufs(&vecs[..n])
+        };
+
+        match r {
+            Ok(n) => Poll::Ready(Ok(n)),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_write_ready(cx)?;
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+
+    pub(crate) fn new(stream: mio_uds::UnixStream) -> io::Result<UnixStream> {
+        let io = PollEvented::new(stream)?;
+        Ok(UnixStream { io })
+    }
+
+    /// Test whether this socket is ready to be read or not.
+    pub fn poll_read_ready(&self, cx: &mut Context<'_>, ready: Ready) -> Poll<Result<Ready, io::Error>> {
+        self.io.poll_read_ready(cx,ready)
+    }
+
+    /// Test whether this socket is ready to be written to or not.
+    pub fn poll_write_ready(&self, cx: &mut Context<'_>) -> Poll<Result<Ready, io::Error>> {
+        self.io.poll_write_ready(cx)
+    }
+
+    /// Returns the socket address of the local half of this connection.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+
+    /// Returns the socket address of the remote half of this connection.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().peer_addr()
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.io.get_ref().take_error()
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    ///
+    /// This function will cause all pending and future I/O calls on the
+    /// specified portions to immediately return with an appropriate value
+    /// (see the documentation of `Shutdown`).
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.io.get_ref().shutdown(how)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+impl crate::ready::PollSocket for UnixStream {
+    type Socket = mio_uds::UnixStream;
+
+    fn poll_evented(&self) -> &PollEvented<mio_uds::UnixStream> {
+        &self.io
+    }
+}
+
+impl AsyncRead for UnixStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        <&UnixStream>::poll_read(Pin::new(&mut &*self), cx, buf)
+    }
+
+    fn poll_read_buf<B: BufMut>(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut B) -> Poll<io::Result<usize>> {
+        <&UnixStream>::poll_read_buf(Pin::new(&mut &*self), cx, buf)
+    }
+}
+
+impl AsyncWrite for UnixStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        <&UnixStream>::poll_write(Pin::new(&mut &*self), cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        <&UnixStream>::poll_flush(Pin::new(&mut &*self), cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        <&UnixStream>::poll_shutdown(Pin::new(&mut &*self), cx)
+    }
+
+    fn poll_write_buf<B: Buf>(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut B) -> Poll<io::Result<usize>> {
+        <&UnixStream>::poll_write_buf(Pin::new(&mut &*self), cx, buf)
+    }
+}
+
+impl AsyncRead for &UnixStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        self.poll_read_vectored(cx, &mut [IoSliceMut::new(buf)])
+    }
+
+    fn poll_read_buf<B: BufMut>(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut B) -> Poll<io::Result<usize>> {
+        let n = {
+            let mut slices: [bytes::buf::IoSliceMut<'_>; MAX_BUFS] =
+                std::array::from_fn(|_| <&mut [u8]>::default().into());
+            let cnt = buf.bytes_vectored_mut(&mut slices);
+            // `bytes` wraps the std type transparently. The memory may be
+            // uninitialized, but `readv` only ever writes to it.
+            let slices = unsafe { &mut *(&mut slices[..cnt] as *mut [bytes::buf::IoSliceMut<'_>] as *mut [IoSliceMut<'_>]) };
+            futures::ready!(self.poll_read_vectored(cx, slices))?
+        };
+
+        unsafe { buf.advance_mut(n) };
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl AsyncWrite for &UnixStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.poll_write_vectored(cx, &[IoSlice::new(buf)])
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(self.shutdown(Shutdown::Write))
+    }
+
+    fn poll_write_buf<B: Buf>(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut B) -> Poll<io::Result<usize>> {
+        let n = {
+            let mut slices: [IoSlice<'_>; MAX_BUFS] = std::array::from_fn(|_| IoSlice::new(&[]));
+            let cnt = buf.bytes_vectored(&mut slices);
+            futures::ready!(self.poll_write_vectored(cx, &slices[..cnt]))?
+        };
+
+        buf.advance(n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl fmt::Debug for UnixStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.io.get_ref().fmt(f)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+impl AsRawFd for UnixStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl AsRawSocket for UnixStream {
+    fn as_raw_socket(&self) -> RawSocket {
+        self.io.get_ref().as_raw_socket()
+    }
+}
+
+impl Future for ConnectFuture {
+    type Output = io::Result<UnixStream>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        use std::mem;
+
+        match self.inner {
+            State::Waiting(ref mut stream) => {
+                if stream.io.poll_write_ready(cx)?.is_pending() {
+                    return Poll::Pending
+                }
+
+                if let Some(e) = stream.io.get_ref().take_error()? {
+                    return Poll::Ready(Err(e))
+                }
+            }
+            State::Error(_) => {
+                let e = match mem::replace(&mut self.inner, State::Empty) {
+                    State::Error(e) => e,
+                    _ => unreachable!(),
+                };
+
+                return Poll::Ready(Err(e))
+            },
+            State::Empty => panic!("can't poll stream twice"),
+        }
+
+        match mem::replace(&mut self.inner, State::Empty) {
+            State::Waiting(stream) => Poll::Ready(Ok(stream)),
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,387 @@
+//! Thin wrappers around the socket calls that neither `std` nor `tokio`
+//! expose.
+
+use std::io;
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+use libc::{c_int, sockaddr_un, socklen_t};
+
+/// Turns the `-1` returned by a failed libc call into the last OS error.
+pub(crate) fn cvt(res: c_int) -> io::Result<c_int> {
+    if res == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(res)
+    }
+}
+
+/// Creates a new, close-on-exec `AF_UNIX` socket of type `ty`.
+pub(crate) fn socket(ty: c_int) -> io::Result<OwnedFd> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    unsafe {
+        let fd = cvt(libc::socket(libc::AF_UNIX, ty | libc::SOCK_CLOEXEC, 0))?;
+        Ok(OwnedFd::from_raw_fd(fd))
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    unsafe {
+        let fd = OwnedFd::from_raw_fd(cvt(libc::socket(libc::AF_UNIX, ty, 0))?);
+        set_cloexec(&fd)?;
+        Ok(fd)
+    }
+}
+
+/// Builds a `sockaddr_un` for the address `name`, which is a path or, if it
+/// starts with a NUL byte, a name in the abstract namespace.
+pub(crate) fn sockaddr_un(name: &[u8]) -> io::Result<(sockaddr_un, socklen_t)> {
+    let mut addr: sockaddr_un = unsafe { mem::zeroed() };
+    addr.sun_family = libc::AF_UNIX as libc::sa_family_t;
+
+    // Pathnames need room for their terminating NUL, abstract names don't.
+    let is_abstract = name.first() == Some(&0);
+    if name.is_empty() || name.len() + (!is_abstract as usize) > addr.sun_path.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                  "socket address must be non-empty and shorter than SUN_LEN"));
+    }
+    if !is_abstract && name.contains(&0) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                  "socket paths must not contain interior NUL bytes"));
+    }
+
+    for (dst, src) in addr.sun_path.iter_mut().zip(name) {
+        *dst = *src as libc::c_char;
+    }
+
+    let offset = addr.sun_path.as_ptr() as usize - &addr as *const _ as usize;
+    let len = offset + name.len() + (!is_abstract as usize);
+    Ok((addr, len as socklen_t))
+}
+
+/// Returns the address of `name` in the abstract namespace, i.e. `name`
+/// prefixed with a NUL byte.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn abstract_name(name: &[u8]) -> Vec<u8> {
+    let mut addr = Vec::with_capacity(name.len() + 1);
+    addr.push(0);
+    addr.extend_from_slice(name);
+    addr
+}
+
+/// Binds `fd` to the address `name`, see [`sockaddr_un`].
+pub(crate) fn bind<F: AsRawFd>(fd: &F, name: &[u8]) -> io::Result<()> {
+    let (addr, len) = sockaddr_un(name)?;
+    cvt(unsafe { libc::bind(fd.as_raw_fd(), &addr as *const _ as *const libc::sockaddr, len) })?;
+    Ok(())
+}
+
+/// Connects `fd` to the address `name`, see [`sockaddr_un`].
+pub(crate) fn connect<F: AsRawFd>(fd: &F, name: &[u8]) -> io::Result<()> {
+    let (addr, len) = sockaddr_un(name)?;
+    cvt(unsafe { libc::connect(fd.as_raw_fd(), &addr as *const _ as *const libc::sockaddr, len) })?;
+    Ok(())
+}
+
+/// Sends `buf` as a single datagram from `fd` to the address `name`, see
+/// [`sockaddr_un`].
+pub(crate) fn send_to<F: AsRawFd>(fd: &F, buf: &[u8], name: &[u8]) -> io::Result<usize> {
+    let (addr, len) = sockaddr_un(name)?;
+    let n = unsafe {
+        libc::sendto(fd.as_raw_fd(), buf.as_ptr() as *const libc::c_void, buf.len(), 0,
+                     &addr as *const _ as *const libc::sockaddr, len)
+    };
+    if n == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+/// Marks the bound socket `fd` as accepting connections.
+pub(crate) fn listen<F: AsRawFd>(fd: &F, backlog: c_int) -> io::Result<()> {
+    cvt(unsafe { libc::listen(fd.as_raw_fd(), backlog) })?;
+    Ok(())
+}
+
+/// Creates a pair of connected, close-on-exec `AF_UNIX` sockets of type `ty`.
+pub(crate) fn socketpair(ty: c_int) -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    unsafe {
+        cvt(libc::socketpair(libc::AF_UNIX, ty | libc::SOCK_CLOEXEC, 0, fds.as_mut_ptr()))?;
+        Ok((OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])))
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    unsafe {
+        cvt(libc::socketpair(libc::AF_UNIX, ty, 0, fds.as_mut_ptr()))?;
+        let pair = (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1]));
+        set_cloexec(&pair.0)?;
+        set_cloexec(&pair.1)?;
+        Ok(pair)
+    }
+}
+
+/// Accepts a connection on the listening socket `fd`, returning a new,
+/// close-on-exec and nonblocking socket.
+pub(crate) fn accept<F: AsRawFd>(fd: &F) -> io::Result<OwnedFd> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    unsafe {
+        let flags = libc::SOCK_CLOEXEC | libc::SOCK_NONBLOCK;
+        let fd = cvt(libc::accept4(fd.as_raw_fd(), std::ptr::null_mut(), std::ptr::null_mut(), flags))?;
+        Ok(OwnedFd::from_raw_fd(fd))
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    unsafe {
+        let fd = cvt(libc::accept(fd.as_raw_fd(), std::ptr::null_mut(), std::ptr::null_mut()))?;
+        let fd = OwnedFd::from_raw_fd(fd);
+        set_cloexec(&fd)?;
+        set_nonblocking(&fd)?;
+        Ok(fd)
+    }
+}
+
+/// Flags for every send, so a closed peer is reported as `EPIPE` rather than
+/// by `SIGPIPE`, where the platform allows it.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "dragonfly",
+          target_os = "openbsd", target_os = "netbsd"))]
+const SEND_FLAGS: c_int = libc::MSG_NOSIGNAL;
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "dragonfly",
+              target_os = "openbsd", target_os = "netbsd")))]
+const SEND_FLAGS: c_int = 0;
+
+/// Sends `buf` on the connected socket `fd`.
+pub(crate) fn send<F: AsRawFd>(fd: &F, buf: &[u8]) -> io::Result<usize> {
+    let n = unsafe { libc::send(fd.as_raw_fd(), buf.as_ptr() as *const libc::c_void, buf.len(), SEND_FLAGS) };
+    if n == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+/// The most descriptors passed in one message, Linux's `SCM_MAX_FD`.
+pub(crate) const MAX_FDS: usize = 253;
+
+/// Sends `buf` on the connected socket `fd`, passing `fds` along with it as
+/// `SCM_RIGHTS`.
+pub(crate) fn send_msg<F: AsRawFd>(fd: &F, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+    if fds.len() > MAX_FDS {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                  format!("at most {} file descriptors can be sent at once", MAX_FDS)));
+    }
+
+    let mut iov = libc::iovec { iov_base: buf.as_ptr() as *mut libc::c_void, iov_len: buf.len() };
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+
+    let mut control;
+    if !fds.is_empty() {
+        control = cmsg_buffer(mem::size_of_val(fds) as u32);
+        unsafe { write_cmsg(&mut msg, &mut control, libc::SCM_RIGHTS, fds) };
+    }
+
+    let n = unsafe { libc::sendmsg(fd.as_raw_fd(), &msg, SEND_FLAGS) };
+    if n == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+/// Sends `buf` on the socket `fd`, attaching the credentials of the calling
+/// process as `SCM_CREDENTIALS`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn send_cred<F: AsRawFd>(fd: &F, buf: &[u8]) -> io::Result<usize> {
+    let cred = unsafe { libc::ucred { pid: libc::getpid(), uid: libc::geteuid(), gid: libc::getegid() } };
+
+    let mut iov = libc::iovec { iov_base: buf.as_ptr() as *mut libc::c_void, iov_len: buf.len() };
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+
+    let mut control = cmsg_buffer(mem::size_of::<libc::ucred>() as u32);
+    unsafe { write_cmsg(&mut msg, &mut control, libc::SCM_CREDENTIALS, &[cred]) };
+
+    let n = unsafe { libc::sendmsg(fd.as_raw_fd(), &msg, SEND_FLAGS) };
+    if n == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+/// Receives into `buf` from `fd`, returning the number of bytes received and
+/// the credentials attached as `SCM_CREDENTIALS`, if any.
+///
+/// Any descriptors passed along are closed.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn recv_cred<F: AsRawFd>(fd: &F, buf: &mut [u8]) -> io::Result<(usize, Option<libc::ucred>)> {
+    let mut iov = libc::iovec { iov_base: buf.as_mut_ptr() as *mut libc::c_void, iov_len: buf.len() };
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+
+    let len = mem::size_of::<libc::ucred>() as u32;
+    let mut control = cmsg_buffer(len);
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = unsafe { libc::CMSG_SPACE(len) } as _;
+
+    let n = unsafe { libc::recvmsg(fd.as_raw_fd(), &mut msg, RECV_FLAGS) };
+    if n == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    drop(unsafe { received_fds(&msg) });
+
+    let mut cred = None;
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_CREDENTIALS {
+                cred = Some((libc::CMSG_DATA(cmsg) as *const libc::ucred).read_unaligned());
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+    Ok((n as usize, cred))
+}
+
+/// Points `msg` at `control` and fills it with a single `SOL_SOCKET` control
+/// message of type `ty` carrying `data`.
+///
+/// `control` must come from [`cmsg_buffer`] for the size of `data`.
+unsafe fn write_cmsg<T>(msg: &mut libc::msghdr, control: &mut [u64], ty: c_int, data: &[T]) {
+    let len = mem::size_of_val(data) as u32;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = libc::CMSG_SPACE(len) as _;
+
+    let cmsg = libc::CMSG_FIRSTHDR(msg);
+    (*cmsg).cmsg_level = libc::SOL_SOCKET;
+    (*cmsg).cmsg_type = ty;
+    (*cmsg).cmsg_len = libc::CMSG_LEN(len) as _;
+    std::ptr::copy_nonoverlapping(data.as_ptr() as *const u8, libc::CMSG_DATA(cmsg), len as usize);
+}
+
+/// Flags for every receive, so passed descriptors are close-on-exec from the
+/// start, where the platform allows it.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const RECV_FLAGS: c_int = libc::MSG_CMSG_CLOEXEC;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const RECV_FLAGS: c_int = 0;
+
+/// Receives into `buf` from `fd` with `recvmsg`, returning the number of
+/// bytes received and the resulting `msg_flags`, e.g. `MSG_TRUNC`.
+///
+/// With `fds`, descriptors passed as `SCM_RIGHTS` are appended to it, all
+/// close-on-exec. If some of them were lost to `MSG_CTRUNC`, the rest are
+/// closed again and this fails with `ErrorKind::InvalidData`. Without `fds`,
+/// the kernel closes any descriptors passed.
+pub(crate) fn recv_msg<F: AsRawFd>(fd: &F, buf: &mut [u8], fds: Option<&mut Vec<OwnedFd>>)
+                                   -> io::Result<(usize, c_int)> {
+    let mut iov = libc::iovec { iov_base: buf.as_mut_ptr() as *mut libc::c_void, iov_len: buf.len() };
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+
+    let mut control;
+    if fds.is_some() {
+        let len = (MAX_FDS * mem::size_of::<RawFd>()) as u32;
+        control = cmsg_buffer(len);
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = unsafe { libc::CMSG_SPACE(len) } as _;
+    }
+
+    let n = unsafe { libc::recvmsg(fd.as_raw_fd(), &mut msg, RECV_FLAGS) };
+    if n == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    if let Some(fds) = fds {
+        let received = unsafe { received_fds(&msg) };
+        if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+            drop(received);
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      "control message truncated, passed file descriptors were lost"));
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        for fd in received.iter() {
+            set_cloexec(fd)?;
+        }
+        fds.extend(received);
+    }
+    Ok((n as usize, msg.msg_flags))
+}
+
+/// Takes ownership of the descriptors passed as `SCM_RIGHTS` in `msg`.
+unsafe fn received_fds(msg: &libc::msghdr) -> Vec<OwnedFd> {
+    let mut fds = Vec::new();
+    let mut cmsg = libc::CMSG_FIRSTHDR(msg);
+    while !cmsg.is_null() {
+        if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+            let data = libc::CMSG_DATA(cmsg) as *const RawFd;
+            let len = (*cmsg).cmsg_len as usize - (data as usize - cmsg as usize);
+            for i in 0..len / mem::size_of::<RawFd>() {
+                fds.push(OwnedFd::from_raw_fd(data.add(i).read_unaligned()));
+            }
+        }
+        cmsg = libc::CMSG_NXTHDR(msg, cmsg);
+    }
+    fds
+}
+
+/// Allocates a zeroed buffer, suitably aligned for `cmsghdr`, with room for
+/// a control message carrying `len` bytes of data.
+fn cmsg_buffer(len: u32) -> Vec<u64> {
+    let space = unsafe { libc::CMSG_SPACE(len) } as usize;
+    vec![0; space.div_ceil(mem::size_of::<u64>())]
+}
+
+/// Reads the integer socket option `name` at `level` from `fd`.
+pub(crate) fn getsockopt_int<F: AsRawFd>(fd: &F, level: c_int, name: c_int) -> io::Result<c_int> {
+    let mut value: c_int = 0;
+    let mut len = mem::size_of::<c_int>() as socklen_t;
+    cvt(unsafe {
+        libc::getsockopt(fd.as_raw_fd(), level, name, &mut value as *mut _ as *mut libc::c_void, &mut len)
+    })?;
+    Ok(value)
+}
+
+/// Sets the integer socket option `name` at `level` on `fd` to `value`.
+pub(crate) fn setsockopt_int<F: AsRawFd>(fd: &F, level: c_int, name: c_int, value: c_int) -> io::Result<()> {
+    cvt(unsafe {
+        libc::setsockopt(fd.as_raw_fd(), level, name, &value as *const _ as *const libc::c_void,
+                         mem::size_of::<c_int>() as socklen_t)
+    })?;
+    Ok(())
+}
+
+/// Returns the address family `fd` was created with.
+pub(crate) fn socket_family<F: AsRawFd>(fd: &F) -> io::Result<c_int> {
+    let mut addr: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::sockaddr_storage>() as socklen_t;
+    cvt(unsafe { libc::getsockname(fd.as_raw_fd(), &mut addr as *mut _ as *mut libc::sockaddr, &mut len) })?;
+    Ok(addr.ss_family as c_int)
+}
+
+/// Sets the close-on-exec flag of `fd`.
+pub(crate) fn set_cloexec<F: AsRawFd>(fd: &F) -> io::Result<()> {
+    let flags = cvt(unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFD) })?;
+    cvt(unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, flags | libc::FD_CLOEXEC) })?;
+    Ok(())
+}
+
+/// Returns `true` if `fd` is an open descriptor of this process.
+pub(crate) fn is_open(fd: RawFd) -> bool {
+    (unsafe { libc::fcntl(fd, libc::F_GETFD) }) != -1
+}
+
+/// Puts `fd` into nonblocking mode.
+pub(crate) fn set_nonblocking<F: AsRawFd>(fd: &F) -> io::Result<()> {
+    let flags = cvt(unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFL) })?;
+    cvt(unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK) })?;
+    Ok(())
+}
@@ -0,0 +1,303 @@
+use std::convert::TryFrom;
+use std::env;
+use std::ffi::OsStr;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{FromRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+use crate::UnixListener;
+
+/// The first descriptor systemd passes, `SD_LISTEN_FDS_START`.
+const LISTEN_FDS_START: RawFd = 3;
+
+/// The sockets systemd passed to this process through socket activation.
+///
+/// Read from the `LISTEN_PID`, `LISTEN_FDS` and `LISTEN_FDNAMES` environment
+/// variables, following `sd_listen_fds_with_names(3)`.
+#[derive(Debug)]
+pub struct ActivatedSockets {
+    sockets: Vec<(String, Option<OwnedFd>)>,
+}
+
+impl ActivatedSockets {
+    /// Takes ownership of the sockets passed to this process.
+    ///
+    /// The environment variables are removed afterwards, so child processes
+    /// don't mistake the sockets for their own and a second call finds none.
+    /// If the variables are missing or meant for another process, the
+    /// result is empty. Fails with `ErrorKind::InvalidData` if they are
+    /// malformed, or name descriptors which aren't open.
+    ///
+    /// Removing environment variables while another thread reads the
+    /// environment is undefined behaviour on most platforms, so call this
+    /// early in `main`, before starting the tokio runtime or any other
+    /// threads.
+    pub fn from_env() -> io::Result<Self> {
+        let sockets = Self::read_env();
+        env::remove_var("LISTEN_PID");
+        env::remove_var("LISTEN_FDS");
+        env::remove_var("LISTEN_FDNAMES");
+        sockets
+    }
+
+    fn read_env() -> io::Result<Self> {
+        let pid = match env::var("LISTEN_PID") {
+            Ok(pid) => pid,
+            Err(_) => return Ok(ActivatedSockets { sockets: Vec::new() })
+        };
+        if parse_var::<u32>("LISTEN_PID", &pid)? != std::process::id() {
+            return Ok(ActivatedSockets { sockets: Vec::new() });
+        }
+
+        let count = match env::var("LISTEN_FDS") {
+            Ok(count) => count,
+            Err(_) => return Ok(ActivatedSockets { sockets: Vec::new() })
+        };
+        let count: u32 = parse_var("LISTEN_FDS", &count)?;
+        let fds = RawFd::try_from(count).ok()
+            .and_then(|count| LISTEN_FDS_START.checked_add(count))
+            .map(|end| LISTEN_FDS_START..end)
+            .ok_or_else(|| invalid_var("LISTEN_FDS", &count))?;
+
+        let names = env::var("LISTEN_FDNAMES").ok();
+        let names: Option<Vec<&str>> = names.as_deref().map(|names| match names {
+            "" => Vec::new(),
+            names => names.split(':').collect()
+        });
+        if let Some(names) = names.as_ref().filter(|names| names.len() != count as usize) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      format!("LISTEN_FDNAMES has {} names for {} sockets", names.len(), count)));
+        }
+
+        // Check every descriptor before owning any, so a bogus count can't
+        // make us close descriptors that belong to someone else.
+        if let Some(fd) = fds.clone().find(|&fd| !crate::sys::is_open(fd)) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      format!("LISTEN_FDS={} but descriptor {} isn't open", count, fd)));
+        }
+
+        let mut sockets = Vec::new();
+        for (i, fd) in fds.enumerate() {
+            let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+            crate::sys::set_cloexec(&fd)?;
+
+            let name = names.as_ref().map_or("unknown", |names| names[i]);
+            sockets.push((name.to_owned(), Some(fd)));
+        }
+        Ok(ActivatedSockets { sockets })
+    }
+
+    /// Returns the number of sockets passed, including those already taken.
+    pub fn len(&self) -> usize {
+        self.sockets.len()
+    }
+
+    /// Returns `true` if no sockets were passed.
+    pub fn is_empty(&self) -> bool {
+        self.sockets.is_empty()
+    }
+
+    /// Returns the names of the sockets passed, in order.
+    ///
+    /// Sockets are named with `FileDescriptorName=` in their unit; unnamed
+    /// ones are called `unknown`.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.sockets.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Takes the first socket called `name` which hasn't been taken yet.
+    ///
+    /// Fails with `ErrorKind::NotFound` if there is no such socket, and with
+    /// `ErrorKind::InvalidInput` if it is not a listening Unix stream socket.
+    pub fn take_listener(&mut self, name: &str) -> io::Result<UnixListener> {
+        let fd = self.sockets.iter_mut()
+            .filter(|(n, _)| n == name)
+            .find_map(|(_, fd)| fd.take())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound,
+                                          format!("no activated socket named {:?}", name)))?;
+        into_listener(fd)
+    }
+
+    /// Turns every socket which hasn't been taken yet into a listener.
+    ///
+    /// Fails if any of them is not a listening Unix stream socket.
+    pub fn into_listeners(self) -> io::Result<Vec<UnixListener>> {
+        self.sockets.into_iter()
+            .filter_map(|(_, fd)| fd)
+            .map(into_listener)
+            .collect()
+    }
+}
+
+fn parse_var<T: std::str::FromStr>(var: &str, value: &str) -> io::Result<T> {
+    value.parse().map_err(|_| invalid_var(var, value))
+}
+
+fn invalid_var<T: std::fmt::Debug + ?Sized>(var: &str, value: &T) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid {}: {:?}", var, value))
+}
+
+/// Checks that `fd` is a listening Unix stream socket and wraps it.
+fn into_listener(fd: OwnedFd) -> io::Result<UnixListener> {
+    match is_unix_listener(&fd) {
+        Ok(true) => UnixListener::from_std_listener(fd.into(), None),
+        Ok(false) => Err(not_a_listener()),
+        Err(ref err) if err.raw_os_error() == Some(libc::ENOTSOCK) => Err(not_a_listener()),
+        Err(err) => Err(err)
+    }
+}
+
+fn is_unix_listener(fd: &OwnedFd) -> io::Result<bool> {
+    Ok(crate::sys::socket_family(fd)? == libc::AF_UNIX
+        && crate::sys::getsockopt_int(fd, libc::SOL_SOCKET, libc::SO_TYPE)? == libc::SOCK_STREAM
+        && crate::sys::getsockopt_int(fd, libc::SOL_SOCKET, libc::SO_ACCEPTCONN)? != 0)
+}
+
+fn not_a_listener() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "activated socket is not a listening Unix stream socket")
+}
+
+/// Sends service state notifications to systemd, following `sd_notify(3)`.
+///
+/// Services with `Type=notify` report that they have finished starting with
+/// [`ready`](Notifier::ready), and those with `WatchdogSec=` must keep
+/// calling [`watchdog`](Notifier::watchdog), see
+/// [`spawn_watchdog`](Notifier::spawn_watchdog).
+#[derive(Clone, Debug)]
+pub struct Notifier {
+    inner: Arc<NotifySocket>,
+}
+
+#[derive(Debug)]
+struct NotifySocket {
+    fd: OwnedFd,
+    addr: Vec<u8>,
+    stopping: AtomicBool,
+}
+
+impl Notifier {
+    /// Creates a notifier for the socket named by `NOTIFY_SOCKET`, or returns
+    /// `None` if the variable is unset, i.e. the service isn't expected to
+    /// send notifications.
+    pub fn from_env() -> io::Result<Option<Self>> {
+        match env::var_os("NOTIFY_SOCKET") {
+            Some(addr) => Notifier::new(addr).map(Some),
+            None => Ok(None)
+        }
+    }
+
+    /// Creates a notifier which sends to the datagram socket at `addr`.
+    ///
+    /// As in `NOTIFY_SOCKET`, `addr` is either a path or, if it starts with
+    /// `@`, a name in the abstract namespace.
+    pub fn new<A: AsRef<OsStr>>(addr: A) -> io::Result<Self> {
+        let addr = addr.as_ref().as_bytes();
+        let addr = match addr.strip_prefix(b"@") {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Some(name) => crate::sys::abstract_name(name),
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            Some(_) => return Err(crate::merge::abstract_unsupported()),
+            None => addr.to_vec()
+        };
+        // Catch malformed addresses here rather than on every send.
+        crate::sys::sockaddr_un(&addr)?;
+
+        // Notifications are sent straight from async tasks, which must not
+        // block when systemd falls behind.
+        let fd = crate::sys::socket(libc::SOCK_DGRAM)?;
+        crate::sys::set_nonblocking(&fd)?;
+
+        Ok(Notifier {
+            inner: Arc::new(NotifySocket {
+                fd,
+                addr,
+                stopping: AtomicBool::new(false),
+            })
+        })
+    }
+
+    /// Sends `state`, one or more newline-separated `VARIABLE=value`
+    /// assignments, as a single notification.
+    ///
+    /// This never blocks, so it is fine to call from async code: if the
+    /// receiving socket's queue is full, it fails with
+    /// `ErrorKind::WouldBlock` instead.
+    pub fn notify(&self, state: &str) -> io::Result<()> {
+        crate::sys::send_to(&self.inner.fd, state.as_bytes(), &self.inner.addr)?;
+        Ok(())
+    }
+
+    /// Tells systemd that the service has finished starting up, `READY=1`.
+    pub fn ready(&self) -> io::Result<()> {
+        self.notify("READY=1")
+    }
+
+    /// Sets the status text shown for the service, `STATUS=...`.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if `status` spans several lines.
+    pub fn status(&self, status: &str) -> io::Result<()> {
+        if status.contains('\n') {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "status text must not contain newlines"));
+        }
+        self.notify(&format!("STATUS={}", status))
+    }
+
+    /// Tells systemd that the service is shutting down, `STOPPING=1`.
+    ///
+    /// Watchdog tasks started from this notifier, or any of its clones, stop
+    /// pinging afterwards.
+    pub fn stopping(&self) -> io::Result<()> {
+        self.inner.stopping.store(true, Ordering::Release);
+        self.notify("STOPPING=1")
+    }
+
+    /// Pings the service watchdog, `WATCHDOG=1`.
+    pub fn watchdog(&self) -> io::Result<()> {
+        self.notify("WATCHDOG=1")
+    }
+
+    /// Returns the watchdog timeout set by `WATCHDOG_USEC`, or `None` if the
+    /// watchdog is disabled or meant for another process, per `WATCHDOG_PID`.
+    pub fn watchdog_timeout() -> io::Result<Option<Duration>> {
+        if let Ok(pid) = env::var("WATCHDOG_PID") {
+            if parse_var::<u32>("WATCHDOG_PID", &pid)? != std::process::id() {
+                return Ok(None);
+            }
+        }
+        match env::var("WATCHDOG_USEC") {
+            Ok(usec) => match parse_var("WATCHDOG_USEC", &usec)? {
+                0 => Ok(None),
+                usec => Ok(Some(Duration::from_micros(usec)))
+            },
+            Err(_) => Ok(None)
+        }
+    }
+
+    /// Spawns a task onto the tokio runtime which pings the watchdog at half
+    /// of `timeout`, starting right away, until
+    /// [`stopping`](Notifier::stopping) is called.
+    ///
+    /// Failed pings are logged at the `warn` level and don't end the task.
+    pub fn spawn_watchdog(&self, timeout: Duration) -> JoinHandle<()> {
+        let notifier = self.clone();
+        let period = (timeout / 2).max(Duration::from_millis(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                if notifier.inner.stopping.load(Ordering::Acquire) {
+                    break;
+                }
+                if let Err(err) = notifier.watchdog() {
+                    log::warn!("failed to ping the systemd watchdog: {}", err);
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,141 @@
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::ready::PollSocket;
+
+/// Credentials of the process on the other end of a Unix socket.
+///
+/// The kernel records them when the connection is made, so they describe
+/// the peer as it was at `connect` time, whatever it does afterwards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct UCred {
+    uid: u32,
+    gid: u32,
+    pid: Option<i32>,
+}
+
+impl UCred {
+    /// Returns the effective user ID of the peer.
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Returns the effective group ID of the peer.
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// Returns the process ID of the peer, on platforms which report it.
+    pub fn pid(&self) -> Option<i32> {
+        self.pid
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl UCred {
+    pub(crate) fn from_ucred(cred: libc::ucred) -> UCred {
+        UCred { uid: cred.uid, gid: cred.gid, pid: Some(cred.pid) }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn peer_cred<F: std::os::unix::io::AsRawFd>(fd: &F) -> std::io::Result<UCred> {
+    use std::mem;
+
+    let mut cred: libc::ucred = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::ucred>() as libc::socklen_t;
+    crate::sys::cvt(unsafe {
+        libc::getsockopt(fd.as_raw_fd(), libc::SOL_SOCKET, libc::SO_PEERCRED,
+                         &mut cred as *mut _ as *mut libc::c_void, &mut len)
+    })?;
+
+    Ok(UCred::from_ucred(cred))
+}
+
+/// Turns `SO_PASSCRED` on or off for `socket`. This and the functions after
+/// it back the credential passing methods of
+/// [`UnixStream`](crate::UnixStream) and [`UnixDatagram`](crate::UnixDatagram).
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn set_passcred<S: std::os::unix::io::AsRawFd>(socket: &S, passcred: bool) -> std::io::Result<()> {
+    crate::sys::setsockopt_int(socket, libc::SOL_SOCKET, libc::SO_PASSCRED, passcred as libc::c_int)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn passcred<S: std::os::unix::io::AsRawFd>(socket: &S) -> std::io::Result<bool> {
+    Ok(crate::sys::getsockopt_int(socket, libc::SOL_SOCKET, libc::SO_PASSCRED)? != 0)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) async fn send_with_creds<S: PollSocket>(socket: &S, buf: &[u8]) -> std::io::Result<usize> {
+    futures::future::poll_fn(|cx| {
+        socket.poll_write_with(cx, |socket| crate::sys::send_cred(socket, buf))
+    }).await
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) async fn recv_with_creds<S: PollSocket>(socket: &S, buf: &mut [u8])
+                                                   -> std::io::Result<(usize, Option<UCred>)> {
+    let (n, cred) = futures::future::poll_fn(|cx| {
+        socket.poll_read_with(cx, |socket| crate::sys::recv_cred(socket, buf))
+    }).await?;
+    Ok((n, cred.map(UCred::from_ucred)))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(crate) fn set_passcred<S>(_socket: &S, _passcred: bool) -> std::io::Result<()> {
+    Err(creds_unsupported())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(crate) fn passcred<S>(_socket: &S) -> std::io::Result<bool> {
+    Err(creds_unsupported())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(crate) async fn send_with_creds<S>(_socket: &S, _buf: &[u8]) -> std::io::Result<usize> {
+    Err(creds_unsupported())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(crate) async fn recv_with_creds<S>(_socket: &S, _buf: &mut [u8]) -> std::io::Result<(usize, Option<UCred>)> {
+    Err(creds_unsupported())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn creds_unsupported() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Unsupported, "passing credentials is only supported on Linux and Android")
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "dragonfly",
+          target_os = "openbsd", target_os = "netbsd"))]
+pub(crate) fn peer_cred<F: std::os::unix::io::AsRawFd>(fd: &F) -> std::io::Result<UCred> {
+    let mut uid = 0;
+    let mut gid = 0;
+    crate::sys::cvt(unsafe { libc::getpeereid(fd.as_raw_fd(), &mut uid, &mut gid) })?;
+
+    Ok(UCred { uid, gid, pid: peer_pid(fd) })
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn peer_pid<F: std::os::unix::io::AsRawFd>(fd: &F) -> Option<i32> {
+    use std::mem;
+
+    let mut pid: libc::pid_t = 0;
+    let mut len = mem::size_of::<libc::pid_t>() as libc::socklen_t;
+    let res = unsafe {
+        libc::getsockopt(fd.as_raw_fd(), libc::SOL_LOCAL, libc::LOCAL_PEERPID,
+                         &mut pid as *mut _ as *mut libc::c_void, &mut len)
+    };
+    if res == 0 { Some(pid) } else { None }
+}
+
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly", target_os = "openbsd", target_os = "netbsd"))]
+fn peer_pid<F: std::os::unix::io::AsRawFd>(_fd: &F) -> Option<i32> {
+    None
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "ios",
+                         target_os = "freebsd", target_os = "dragonfly", target_os = "openbsd",
+                         target_os = "netbsd"))))]
+pub(crate) fn peer_cred<F: std::os::unix::io::AsRawFd>(_fd: &F) -> std::io::Result<UCred> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported,
+                            "peer credentials are not supported on this platform"))
+}
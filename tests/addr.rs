@@ -0,0 +1,70 @@
+#![cfg(unix)]
+
+use tempfile::Builder;
+use tokio_agnostic_uds::*;
+
+#[test]
+fn pathname() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("addr.sock");
+
+    let listener = std::os::unix::net::UnixListener::bind(&sock_path).unwrap();
+    let addr = SocketAddr::from(listener.local_addr().unwrap());
+    assert_eq!(addr.as_pathname(), Some(sock_path.as_path()));
+    assert!(!addr.is_unnamed());
+    assert_eq!(addr.as_abstract_name(), None);
+    assert_eq!(addr.to_string(), sock_path.display().to_string());
+}
+
+#[test]
+fn unnamed() {
+    let (stream, _) = std::os::unix::net::UnixStream::pair().unwrap();
+    let addr = SocketAddr::from(stream.local_addr().unwrap());
+    assert_eq!(addr.as_pathname(), None);
+    assert!(addr.is_unnamed());
+    assert_eq!(addr.to_string(), "(unnamed)");
+    assert_eq!(format!("{:?}", addr), format!("{:?}", stream.local_addr().unwrap()));
+}
+
+#[tokio::test]
+async fn accepted_peer() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("peer.sock");
+
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    let client = UnixStream::connect(&sock_path).await.unwrap();
+    let (_stream, addr) = server.accept().await.unwrap();
+    assert!(addr.is_unnamed());
+    assert_eq!(client.peer_addr().unwrap().to_string(), sock_path.display().to_string());
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[tokio::test]
+async fn abstract_name() {
+    let name = format!("tokio-uds-tests-{}", std::process::id());
+
+    let mut server = UnixListener::bind_abstract(&name).unwrap();
+    let addr = server.local_addr().unwrap();
+    assert_eq!(addr.as_abstract_name(), Some(name.as_bytes()));
+    assert_eq!(addr.as_pathname(), None);
+    assert!(!addr.is_unnamed());
+    assert_eq!(addr.to_string(), format!("@{}", name));
+
+    let client = UnixStream::connect_abstract(&name).await.unwrap();
+    server.accept().await.unwrap();
+    assert_eq!(client.peer_addr().unwrap().as_abstract_name(), Some(name.as_bytes()));
+
+    let err = UnixStream::connect_abstract(format!("{}-missing", name)).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[tokio::test]
+async fn abstract_name_display_escapes() {
+    let name = format!("tokio-uds-tests-{}\0\u{7f}", std::process::id()).into_bytes();
+
+    let server = UnixListener::bind_abstract(&name).unwrap();
+    let addr = server.local_addr().unwrap();
+    assert_eq!(addr.as_abstract_name(), Some(&name[..]));
+    assert_eq!(addr.to_string(), format!("@tokio-uds-tests-{}\\x00\\x7f", std::process::id()));
+}
@@ -0,0 +1,67 @@
+#![cfg(unix)]
+
+use std::io;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+use tempfile::Builder;
+use tokio_agnostic_uds::*;
+
+mod common;
+
+#[tokio::test]
+async fn mode_and_parents() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("run/app/mode.sock");
+
+    let mut server = UnixListenerBuilder::new()
+        .mode(0o660)
+        .create_parents(true)
+        .bind(&sock_path)
+        .unwrap();
+    let meta = std::fs::metadata(&sock_path).unwrap();
+    assert_eq!(meta.permissions().mode() & 0o777, 0o660);
+
+    let _client = UnixStream::connect(&sock_path).await.unwrap();
+    server.accept().await.unwrap();
+}
+
+#[tokio::test]
+async fn ownership() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("owned.sock");
+
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let _server = UnixListenerBuilder::new().owner(uid).group(gid).bind(&sock_path).unwrap();
+    let meta = std::fs::metadata(&sock_path).unwrap();
+    assert_eq!((meta.uid(), meta.gid()), (uid, gid));
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[tokio::test]
+async fn backlog() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("backlog.sock");
+
+    // Linux queues one connection more than the backlog.
+    let _server = UnixListenerBuilder::new().backlog(2).bind(&sock_path).unwrap();
+    assert_eq!(common::fill_backlog(&sock_path).len(), 3);
+}
+
+#[tokio::test]
+async fn unlink_policies() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("policy.sock");
+
+    let first = UnixListenerBuilder::new().remove_on_drop(true).bind(&sock_path).unwrap();
+    let err = UnixListenerBuilder::new().bind(&sock_path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+    let err = UnixListenerBuilder::new().unlink_existing(UnlinkPolicy::IfStale).bind(&sock_path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+
+    let second = UnixListenerBuilder::new().unlink_existing(UnlinkPolicy::Always).bind(&sock_path).unwrap();
+    drop(first);
+    assert!(sock_path.exists(), "removed the socket file of its replacement");
+    drop(second);
+
+    let _third = UnixListenerBuilder::new().unlink_existing(UnlinkPolicy::IfStale).bind(&sock_path).unwrap();
+}
@@ -0,0 +1,32 @@
+//! Helpers shared by the integration tests.
+
+/// Connects to `path` without blocking until the listener's backlog is full.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn fill_backlog(path: &std::path::Path) -> Vec<std::os::unix::io::OwnedFd> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+
+    let mut addr: libc::sockaddr_un = unsafe { std::mem::zeroed() };
+    addr.sun_family = libc::AF_UNIX as libc::sa_family_t;
+    for (dst, src) in addr.sun_path.iter_mut().zip(path.as_os_str().as_bytes()) {
+        *dst = *src as libc::c_char;
+    }
+
+    let mut clients = Vec::new();
+    loop {
+        let fd = unsafe { libc::socket(libc::AF_UNIX, libc::SOCK_STREAM | libc::SOCK_NONBLOCK, 0) };
+        if fd == -1 {
+            panic!("failed to create a client socket: {}", std::io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        let res = unsafe {
+            libc::connect(fd.as_raw_fd(), &addr as *const _ as *const libc::sockaddr,
+                          std::mem::size_of::<libc::sockaddr_un>() as libc::socklen_t)
+        };
+        if res == -1 {
+            assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EAGAIN));
+            return clients;
+        }
+        clients.push(fd);
+    }
+}
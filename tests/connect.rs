@@ -0,0 +1,128 @@
+#![cfg(unix)]
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use tempfile::Builder;
+use tokio_agnostic_uds::*;
+
+fn connect_error(err: &io::Error) -> &ConnectError {
+    err.get_ref().and_then(|err| err.downcast_ref::<ConnectError>()).expect("not a ConnectError")
+}
+
+#[tokio::test]
+async fn retries_until_the_listener_is_bound() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("late.sock");
+
+    let server_path = sock_path.clone();
+    let server = tokio::spawn(async move {
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+        let mut server = UnixListener::bind(&server_path).unwrap();
+        server.accept().await.unwrap();
+    });
+    ConnectOptions::new()
+        .max_attempts(None)
+        .timeout(Some(Duration::from_secs(5)))
+        .backoff(Duration::from_millis(10), Duration::from_millis(20))
+        .connect(&sock_path).await
+        .unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn reports_every_attempt() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("missing.sock");
+
+    let err = ConnectOptions::new()
+        .max_attempts(Some(3))
+        .backoff(Duration::from_millis(1), Duration::from_millis(2))
+        .connect(&sock_path).await
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    let attempts = connect_error(&err).attempts();
+    assert_eq!(attempts.len(), 3);
+    assert!(attempts.iter().all(|err| err.kind() == io::ErrorKind::NotFound));
+
+    let reason = attempts[0].to_string();
+    assert_eq!(err.to_string(),
+               format!("failed to connect to {} after 3 attempts: attempt 1: {}; attempt 2: {}; attempt 3: {}",
+                       sock_path.display(), reason, reason, reason));
+}
+
+#[tokio::test]
+async fn retries_refused_connections() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("stale.sock");
+    drop(std::os::unix::net::UnixListener::bind(&sock_path).unwrap());
+
+    let err = ConnectOptions::new()
+        .max_attempts(Some(2))
+        .backoff(Duration::from_millis(1), Duration::from_millis(2))
+        .connect(&sock_path).await
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+    assert_eq!(connect_error(&err).attempts().len(), 2);
+}
+
+#[tokio::test]
+async fn gives_up_at_the_deadline() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("missing.sock");
+
+    let start = Instant::now();
+    let err = ConnectOptions::new()
+        .max_attempts(None)
+        .timeout(Some(Duration::from_millis(200)))
+        .backoff(Duration::from_millis(10), Duration::from_millis(50))
+        .connect(&sock_path).await
+        .unwrap_err();
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    let attempts = connect_error(&err).attempts();
+    assert!(attempts.len() > 1);
+    assert!(attempts.iter().all(|err| err.kind() == io::ErrorKind::NotFound));
+}
+
+#[tokio::test]
+async fn does_not_retry_other_errors() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let file_path = dir.path().join("file");
+    std::fs::write(&file_path, b"").unwrap();
+
+    let err = ConnectOptions::new()
+        .max_attempts(None)
+        .connect(file_path.join("below-a-file.sock")).await
+        .unwrap_err();
+    let attempts = connect_error(&err).attempts();
+    assert_eq!(attempts.len(), 1);
+    assert_eq!(attempts[0].raw_os_error(), Some(libc::ENOTDIR));
+    assert!(err.to_string().contains("after 1 attempt: attempt 1:"));
+}
+
+#[tokio::test]
+async fn accepts_huge_durations() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("missing.sock");
+
+    // `Duration::MAX` means no limit rather than overflowing.
+    let err = ConnectOptions::new()
+        .timeout(Some(Duration::MAX))
+        .attempt_timeout(Some(Duration::MAX))
+        .max_attempts(Some(2))
+        .backoff(Duration::from_millis(1), Duration::from_millis(2))
+        .connect(&sock_path).await
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    assert_eq!(connect_error(&err).attempts().len(), 2);
+
+    // A backoff too long to double, or to wait out before the deadline.
+    let err = ConnectOptions::new()
+        .timeout(Some(Duration::from_millis(100)))
+        .backoff(Duration::MAX, Duration::MAX)
+        .connect(&sock_path).await
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    assert_eq!(connect_error(&err).attempts().len(), 1);
+}
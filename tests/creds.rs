@@ -0,0 +1,74 @@
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use std::io::Read;
+use std::os::unix::io::AsRawFd;
+
+use tokio::io::AsyncWriteExt;
+use tokio_agnostic_uds::*;
+
+fn assert_is_us(cred: Option<UCred>) {
+    let cred = cred.expect("no credentials received");
+    assert_eq!(cred.pid(), Some(std::process::id() as i32));
+    assert_eq!(cred.uid(), unsafe { libc::geteuid() });
+    assert_eq!(cred.gid(), unsafe { libc::getegid() });
+}
+
+#[tokio::test]
+async fn stream_passes_creds() {
+    let (mut a, b) = UnixStream::pair().unwrap();
+    assert!(!b.passcred().unwrap());
+    a.send_with_creds(b"off").await.unwrap();
+    let mut buf = [0; 16];
+    assert_eq!(b.recv_with_creds(&mut buf).await.unwrap(), (3, None));
+
+    b.set_passcred(true).unwrap();
+    assert!(b.passcred().unwrap());
+    a.send_with_creds(b"explicit").await.unwrap();
+    let (n, cred) = b.recv_with_creds(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"explicit");
+    assert_is_us(cred);
+
+    // With `SO_PASSCRED` on, even plain writes carry credentials.
+    a.write_all(b"implicit").await.unwrap();
+    let (n, cred) = b.recv_with_creds(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"implicit");
+    assert_is_us(cred);
+}
+
+#[tokio::test]
+async fn datagram_passes_creds() {
+    let (a, b) = UnixDatagram::pair().unwrap();
+    a.send(b"off").await.unwrap();
+    let mut buf = [0; 16];
+    assert_eq!(b.recv_with_creds(&mut buf).await.unwrap(), (3, None));
+
+    b.set_passcred(true).unwrap();
+    assert!(b.passcred().unwrap());
+    a.send_with_creds(b"explicit").await.unwrap();
+    let (n, cred) = b.recv_with_creds(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"explicit");
+    assert_is_us(cred);
+
+    a.send(b"implicit").await.unwrap();
+    let (n, cred) = b.recv_with_creds(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"implicit");
+    assert_is_us(cred);
+}
+
+#[tokio::test]
+async fn recv_with_creds_closes_passed_fds() {
+    // Without `SO_PASSCRED`, the descriptors get the buffer meant for the
+    // credentials to themselves.
+    let (a, b) = UnixDatagram::pair().unwrap();
+    let (passed, mut peer) = std::os::unix::net::UnixStream::pair().unwrap();
+    peer.set_nonblocking(true).unwrap();
+
+    let fd = passed.as_raw_fd();
+    a.send_with_fds(b"fds", &[fd, fd, fd]).await.unwrap();
+    let mut buf = [0; 16];
+    assert_eq!(b.recv_with_creds(&mut buf).await.unwrap(), (3, None));
+
+    // Once the original is closed too, no copy of it is left open.
+    drop(passed);
+    assert_eq!(peer.read(&mut buf).expect("a passed descriptor was leaked"), 0);
+}
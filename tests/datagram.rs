@@ -0,0 +1,98 @@
+#![cfg(unix)]
+
+use tempfile::Builder;
+use tokio_agnostic_uds::*;
+
+#[tokio::test]
+async fn send_to_and_recv_from() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let server_path = dir.path().join("server.sock");
+    let client_path = dir.path().join("client.sock");
+
+    let server = UnixDatagram::bind(&server_path).unwrap();
+    let client = UnixDatagram::bind(&client_path).unwrap();
+    assert_eq!(server.local_addr().unwrap().as_pathname(), Some(server_path.as_path()));
+
+    assert_eq!(client.send_to(b"request", &server_path).await.unwrap(), 7);
+    let mut buf = [0; 16];
+    let (n, addr) = server.recv_from(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"request");
+    assert_eq!(addr.as_pathname(), Some(client_path.as_path()));
+
+    server.send_to(b"reply", addr.as_pathname().unwrap()).await.unwrap();
+    let (n, addr) = client.recv_from(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"reply");
+    assert_eq!(addr.as_pathname(), Some(server_path.as_path()));
+}
+
+#[tokio::test]
+async fn unbound_sender() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let server_path = dir.path().join("server.sock");
+
+    let server = UnixDatagram::bind(&server_path).unwrap();
+    let client = UnixDatagram::unbound().unwrap();
+    client.send_to(b"anonymous", &server_path).await.unwrap();
+
+    let mut buf = [0; 16];
+    let (n, addr) = server.recv_from(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"anonymous");
+    assert!(addr.is_unnamed());
+}
+
+#[tokio::test]
+async fn connected_pair() {
+    let (a, b) = UnixDatagram::pair().unwrap();
+    a.send(b"first").await.unwrap();
+    a.send(b"second").await.unwrap();
+
+    // Each receive takes exactly one datagram, discarding what doesn't fit.
+    let mut buf = [0; 3];
+    assert_eq!(b.recv(&mut buf).await.unwrap(), 3);
+    assert_eq!(&buf, b"fir");
+    let mut buf = [0; 16];
+    let n = b.recv(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"second");
+}
+
+#[tokio::test]
+async fn connect() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let server_path = dir.path().join("server.sock");
+
+    let server = UnixDatagram::bind(&server_path).unwrap();
+    let client = UnixDatagram::unbound().unwrap();
+    client.connect(&server_path).unwrap();
+    assert_eq!(client.peer_addr().unwrap().as_pathname(), Some(server_path.as_path()));
+
+    client.send(b"hello").await.unwrap();
+    let mut buf = [0; 16];
+    let n = server.recv(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"hello");
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[tokio::test]
+async fn abstract_peers() {
+    use std::os::linux::net::SocketAddrExt;
+
+    let bind_abstract = |name: &str| {
+        let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+        UnixDatagram::from_std(std::os::unix::net::UnixDatagram::bind_addr(&addr).unwrap()).unwrap()
+    };
+    let server_name = format!("tokio-uds-tests-dgram-server-{}", std::process::id());
+    let client_name = format!("tokio-uds-tests-dgram-client-{}", std::process::id());
+    let server = bind_abstract(&server_name);
+    let client = bind_abstract(&client_name);
+
+    client.send_to_abstract(b"request", &server_name).await.unwrap();
+    let mut buf = [0; 16];
+    let (n, addr) = server.recv_from(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"request");
+    assert_eq!(addr.as_abstract_name(), Some(client_name.as_bytes()));
+
+    // Abstract senders can be replied to.
+    server.send_to_abstract(b"reply", addr.as_abstract_name().unwrap()).await.unwrap();
+    let n = client.recv(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"reply");
+}
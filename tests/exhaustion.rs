@@ -0,0 +1,139 @@
+#![cfg(unix)]
+
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use tempfile::Builder;
+use tokio_agnostic_uds::*;
+
+/// The file descriptor limit is process-wide, so tests lowering it take
+/// turns, each on its own runtime.
+static RLIMIT: Mutex<()> = Mutex::new(());
+
+fn exclusively<F: std::future::Future>(test: F) -> F::Output {
+    let _lock = RLIMIT.lock().unwrap_or_else(|err| err.into_inner());
+    tokio::runtime::Builder::new().basic_scheduler().enable_all().build().unwrap().block_on(test)
+}
+
+/// Returns the descriptor the next `open` would get.
+fn lowest_free() -> RawFd {
+    unsafe {
+        let fd = libc::dup(0);
+        assert!(fd >= 0);
+        libc::close(fd);
+        fd
+    }
+}
+
+/// Lowers the soft file descriptor limit until no further descriptor can be
+/// opened, restoring it when dropped.
+struct Exhausted(libc::rlimit);
+
+impl Exhausted {
+    fn new() -> Exhausted {
+        Exhausted::leaving(0)
+    }
+
+    /// Lowers the limit so only `spare` more descriptors can be opened.
+    fn leaving(spare: usize) -> Exhausted {
+        unsafe {
+            let mut old = std::mem::zeroed::<libc::rlimit>();
+            assert_eq!(libc::getrlimit(libc::RLIMIT_NOFILE, &mut old), 0);
+            let new = libc::rlimit { rlim_cur: (lowest_free() as usize + spare) as libc::rlim_t, rlim_max: old.rlim_max };
+            assert_eq!(libc::setrlimit(libc::RLIMIT_NOFILE, &new), 0);
+            Exhausted(old)
+        }
+    }
+}
+
+impl Drop for Exhausted {
+    fn drop(&mut self) {
+        unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &self.0) };
+    }
+}
+
+#[test]
+fn accept_errors_do_not_end_the_stream() {
+    exclusively(async {
+        let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+        let sock_path = dir.path().join("exhausted.sock");
+
+        let mut server = UnixListener::bind(&sock_path).unwrap();
+        server.set_accept_backoff(None);
+        let _first = std::os::unix::net::UnixStream::connect(&sock_path).unwrap();
+        let _second = std::os::unix::net::UnixStream::connect(&sock_path).unwrap();
+
+        let exhausted = Exhausted::new();
+        let err = server.next().await.expect("stream ended on an accept error").unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EMFILE));
+        assert!(is_transient_accept_error(&err));
+        drop(exhausted);
+
+        server.next().await.expect("stream ended after an accept error").unwrap();
+        server.accept().await.unwrap();
+    });
+}
+
+#[test]
+fn accept_backs_off_while_exhausted() {
+    exclusively(async {
+        let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+        let sock_path = dir.path().join("backoff.sock");
+
+        let mut server = UnixListener::bind(&sock_path).unwrap();
+        server.set_accept_backoff(Some(Duration::from_millis(50)));
+        let _client = std::os::unix::net::UnixStream::connect(&sock_path).unwrap();
+
+        let exhausted = Exhausted::new();
+        let start = Instant::now();
+        let restore = tokio::spawn(async move {
+            tokio::time::delay_for(Duration::from_millis(120)).await;
+            drop(exhausted);
+        });
+        server.accept().await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        restore.await.unwrap();
+    });
+}
+
+#[test]
+fn passed_fds_beyond_the_limit_are_not_leaked() {
+    exclusively(async {
+        let (a, b) = UnixStream::pair().unwrap();
+        let (c, d) = UnixDatagram::pair().unwrap();
+        let file = tempfile::tempfile().unwrap();
+        let fd = file.as_raw_fd();
+        a.send_with_fds(b"x", &[fd, fd]).await.unwrap();
+        c.send_with_fds(b"x", &[fd, fd]).await.unwrap();
+
+        // Only the first of each pair fits, so both are closed again.
+        let free = lowest_free();
+        let exhausted = Exhausted::leaving(1);
+        let mut fds = Vec::new();
+        let mut buf = [0; 1];
+        let err = b.recv_with_fds(&mut buf, &mut fds).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = d.recv_with_fds(&mut buf, &mut fds).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(fds.is_empty());
+        drop(exhausted);
+        assert_eq!(lowest_free(), free);
+
+        // The connection itself still works.
+        a.send_with_fds(b"y", &[fd]).await.unwrap();
+        assert_eq!(b.recv_with_fds(&mut buf, &mut fds).await.unwrap(), 1);
+        assert_eq!(&buf, b"y");
+        assert_eq!(fds.len(), 1);
+    });
+}
+
+#[test]
+fn transient_accept_errors() {
+    assert!(is_transient_accept_error(&io::Error::from(io::ErrorKind::ConnectionAborted)));
+    assert!(is_transient_accept_error(&io::Error::from_raw_os_error(libc::ENFILE)));
+    assert!(!is_transient_accept_error(&io::Error::from_raw_os_error(libc::EBADF)));
+    assert!(!is_transient_accept_error(&io::Error::from(io::ErrorKind::InvalidInput)));
+}
@@ -0,0 +1,75 @@
+#![cfg(unix)]
+
+use std::io::{self, Read, Seek, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use tokio::io::AsyncReadExt;
+use tokio_agnostic_uds::*;
+
+fn is_cloexec(fd: RawFd) -> bool {
+    unsafe { libc::fcntl(fd, libc::F_GETFD) & libc::FD_CLOEXEC != 0 }
+}
+
+#[tokio::test]
+async fn stream_passes_fds() {
+    let (a, mut b) = UnixStream::pair().unwrap();
+    let mut file = tempfile::tempfile().unwrap();
+    file.write_all(b"contents").unwrap();
+    let (socket, mut peer) = std::os::unix::net::UnixStream::pair().unwrap();
+
+    assert_eq!(a.send_with_fds(b"fds", &[file.as_raw_fd(), socket.as_raw_fd()]).await.unwrap(), 3);
+    let mut fds = Vec::new();
+    let mut buf = [0; 16];
+    assert_eq!(b.recv_with_fds(&mut buf, &mut fds).await.unwrap(), 3);
+    assert_eq!(&buf[..3], b"fds");
+    assert_eq!(fds.len(), 2);
+    assert!(fds.iter().all(|fd| is_cloexec(fd.as_raw_fd())));
+
+    // The received descriptors refer to the same file and socket.
+    let mut socket = std::os::unix::net::UnixStream::from(fds.pop().unwrap());
+    let mut file = std::fs::File::from(fds.pop().unwrap());
+    file.rewind().unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "contents");
+    socket.write_all(b"hi").unwrap();
+    let mut buf = [0; 2];
+    peer.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hi");
+
+    // Plain reads see the bytes but not the descriptors.
+    a.send_with_fds(b"more", &[file.as_raw_fd()]).await.unwrap();
+    let mut buf = [0; 4];
+    b.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"more");
+}
+
+#[tokio::test]
+async fn datagram_passes_fds() {
+    let (a, b) = UnixDatagram::pair().unwrap();
+    let file = tempfile::tempfile().unwrap();
+
+    a.send_with_fds(b"one", &[file.as_raw_fd()]).await.unwrap();
+    a.send_with_fds(b"two", &[file.as_raw_fd(), file.as_raw_fd()]).await.unwrap();
+    let mut fds = Vec::new();
+    let mut buf = [0; 16];
+    assert_eq!(b.recv_with_fds(&mut buf, &mut fds).await.unwrap(), 3);
+    assert_eq!(fds.len(), 1);
+    assert_eq!(b.recv_with_fds(&mut buf, &mut fds).await.unwrap(), 3);
+    assert_eq!(&buf[..3], b"two");
+    assert_eq!(fds.len(), 3);
+    assert!(fds.iter().all(|fd| is_cloexec(fd.as_raw_fd())));
+}
+
+#[tokio::test]
+async fn rejects_unsendable_fds() {
+    let (a, _b) = UnixStream::pair().unwrap();
+    let file = tempfile::tempfile().unwrap();
+
+    let err = a.send_with_fds(b"", &[file.as_raw_fd()]).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    let err = a.send_with_fds(b"x", &[file.as_raw_fd(); 254]).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    let err = a.send_with_fds(b"x", &[-1]).await.unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+}
@@ -0,0 +1,47 @@
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use tempfile::Builder;
+use tokio_agnostic_uds::*;
+
+#[tokio::test]
+async fn blocks_at_limit_until_a_connection_drops() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("limit.sock");
+
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    server.set_max_connections(1);
+    let _first = UnixStream::connect(&sock_path).await.unwrap();
+    let _second = UnixStream::connect(&sock_path).await.unwrap();
+
+    let (first, _) = server.accept().await.unwrap();
+    assert_eq!(server.live_connections(), 1);
+    assert!(tokio::time::timeout(Duration::from_millis(50), server.accept()).await.is_err(),
+            "accepted past the limit");
+
+    let accept = tokio::spawn(async move {
+        let (second, _) = server.accept().await.unwrap();
+        (server, second)
+    });
+    tokio::time::delay_for(Duration::from_millis(20)).await;
+    drop(first);
+    let (server, second) = accept.await.unwrap();
+    assert_eq!(server.live_connections(), 1);
+
+    drop(second);
+    assert_eq!(server.live_connections(), 0);
+}
+
+#[tokio::test]
+async fn into_std_releases_its_slot() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("released.sock");
+
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    server.set_max_connections(1);
+    let _client = UnixStream::connect(&sock_path).await.unwrap();
+    let (stream, _) = server.accept().await.unwrap();
+    let _stream = stream.into_std().unwrap();
+    assert_eq!(server.live_connections(), 0);
+}
@@ -0,0 +1,78 @@
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use tempfile::Builder;
+use tokio_agnostic_uds::*;
+
+#[tokio::test]
+async fn accept() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("accept.sock");
+
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    let client = tokio::spawn(async move { UnixStream::connect(&sock_path).await.unwrap() });
+
+    let (_stream, addr) = server.accept().await.unwrap();
+    assert!(addr.is_unnamed());
+    client.await.unwrap();
+}
+
+#[tokio::test]
+async fn accept_is_cancellable() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("cancel.sock");
+
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    tokio::select! {
+        _ = server.accept() => panic!("accepted without a client"),
+        _ = tokio::time::delay_for(Duration::from_millis(20)) => {}
+    }
+
+    let _client = UnixStream::connect(&sock_path).await.unwrap();
+    server.accept().await.unwrap();
+}
+
+#[tokio::test]
+async fn incoming() {
+    use futures::StreamExt;
+
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("incoming.sock");
+
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    let _client = UnixStream::connect(&sock_path).await.unwrap();
+
+    let mut incoming: Incoming<'_> = server.incoming();
+    let conn: Option<std::io::Result<(UnixStream, SocketAddr)>> = incoming.next().await;
+    let (_stream, addr) = conn.unwrap().unwrap();
+    assert!(addr.is_unnamed());
+
+    // The listener is only borrowed, so it keeps accepting afterwards.
+    let _client = UnixStream::connect(&sock_path).await.unwrap();
+    server.accept().await.unwrap();
+}
+
+#[tokio::test]
+async fn addresses_and_shutdown() {
+    use std::net::Shutdown;
+    use tokio::io::AsyncReadExt;
+
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("shutdown.sock");
+
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    assert_eq!(server.local_addr().unwrap().as_pathname(), Some(sock_path.as_path()));
+    assert!(server.take_error().unwrap().is_none());
+
+    let client = UnixStream::connect(&sock_path).await.unwrap();
+    let (mut stream, _) = server.accept().await.unwrap();
+    assert_eq!(client.peer_addr().unwrap().as_pathname(), Some(sock_path.as_path()));
+    assert_eq!(stream.local_addr().unwrap().as_pathname(), Some(sock_path.as_path()));
+    assert!(stream.peer_addr().unwrap().is_unnamed());
+    assert!(stream.take_error().unwrap().is_none());
+
+    client.shutdown(Shutdown::Write).unwrap();
+    let mut buf = [0; 4];
+    assert_eq!(stream.read(&mut buf).await.unwrap(), 0);
+}
@@ -0,0 +1,94 @@
+#![cfg(unix)]
+
+use std::io;
+use std::time::Duration;
+
+use tempfile::Builder;
+use tokio_agnostic_uds::*;
+
+/// Receives the next notification, as systemd would.
+async fn recv(socket: &UnixDatagram) -> Vec<u8> {
+    let mut buf = [0; 256];
+    let n = tokio::time::timeout(Duration::from_secs(5), socket.recv(&mut buf)).await
+        .expect("no notification arrived")
+        .unwrap();
+    buf[..n].to_vec()
+}
+
+#[tokio::test]
+async fn sends_states() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("notify.sock");
+
+    let systemd = UnixDatagram::bind(&sock_path).unwrap();
+    let notifier = Notifier::new(&sock_path).unwrap();
+
+    notifier.ready().unwrap();
+    assert_eq!(recv(&systemd).await, b"READY=1");
+    notifier.status("Serving 3 clients").unwrap();
+    assert_eq!(recv(&systemd).await, b"STATUS=Serving 3 clients");
+    notifier.watchdog().unwrap();
+    assert_eq!(recv(&systemd).await, b"WATCHDOG=1");
+    notifier.stopping().unwrap();
+    assert_eq!(recv(&systemd).await, b"STOPPING=1");
+    notifier.notify("RELOADING=1\nSTATUS=Reloading").unwrap();
+    assert_eq!(recv(&systemd).await, b"RELOADING=1\nSTATUS=Reloading");
+
+    let err = notifier.status("two\nlines").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[tokio::test]
+async fn sends_to_abstract_address() {
+    use std::os::linux::net::SocketAddrExt;
+
+    let name = format!("tokio-uds-tests-notify-{}", std::process::id());
+    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+    let systemd = UnixDatagram::from_std(std::os::unix::net::UnixDatagram::bind_addr(&addr).unwrap()).unwrap();
+    let notifier = Notifier::new(format!("@{}", name)).unwrap();
+
+    notifier.ready().unwrap();
+    assert_eq!(recv(&systemd).await, b"READY=1");
+}
+
+#[tokio::test]
+async fn watchdog_stops_after_stopping() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("watchdog.sock");
+
+    let systemd = UnixDatagram::bind(&sock_path).unwrap();
+    let notifier = Notifier::new(&sock_path).unwrap();
+
+    let watchdog = notifier.spawn_watchdog(Duration::from_millis(40));
+    assert_eq!(recv(&systemd).await, b"WATCHDOG=1");
+    assert_eq!(recv(&systemd).await, b"WATCHDOG=1");
+
+    notifier.clone().stopping().unwrap();
+    tokio::time::timeout(Duration::from_secs(5), watchdog).await.unwrap().unwrap();
+    loop {
+        let msg = recv(&systemd).await;
+        if msg == b"STOPPING=1" {
+            break;
+        }
+        assert_eq!(msg, b"WATCHDOG=1");
+    }
+    let mut buf = [0; 256];
+    assert!(tokio::time::timeout(Duration::from_millis(100), systemd.recv(&mut buf)).await.is_err(),
+            "pinged the watchdog after stopping");
+}
+
+#[tokio::test]
+async fn never_blocks() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("full.sock");
+
+    let _systemd = UnixDatagram::bind(&sock_path).unwrap();
+    let notifier = Notifier::new(&sock_path).unwrap();
+    let err = loop {
+        if let Err(err) = notifier.watchdog() {
+            break err;
+        }
+    };
+    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+}
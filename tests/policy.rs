@@ -0,0 +1,71 @@
+#![cfg(unix)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tempfile::Builder;
+use tokio::io::AsyncReadExt;
+use tokio_agnostic_uds::*;
+
+#[tokio::test]
+async fn rejects_before_accept() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("reject.sock");
+
+    let uid = unsafe { libc::getuid() };
+    let rejected = Arc::new(AtomicUsize::new(0));
+    let on_reject = rejected.clone();
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    server.set_access_policy(AccessPolicy::new()
+        .allow_uid(uid.wrapping_add(1))
+        .on_reject(move |cred| {
+            assert_eq!(cred.uid(), uid);
+            on_reject.fetch_add(1, Ordering::SeqCst);
+        }));
+
+    let mut client = UnixStream::connect(&sock_path).await.unwrap();
+    let accepted = tokio::time::timeout(Duration::from_millis(100), server.accept()).await;
+    assert!(accepted.is_err(), "accepted a rejected peer");
+    assert_eq!(rejected.load(Ordering::SeqCst), 1);
+
+    // The rejected connection has been closed.
+    let mut buf = [0; 1];
+    assert_eq!(client.read(&mut buf).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn allows_matching_peers() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("allow.sock");
+
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    server.set_access_policy(AccessPolicy::new()
+        .allow_uid(uid.wrapping_add(1))
+        .allow_uid(uid)
+        .allow_gid(gid)
+        .custom(move |cred| cred.uid() == uid));
+
+    let _client = UnixStream::connect(&sock_path).await.unwrap();
+    let (stream, _) = server.accept().await.unwrap();
+    assert_eq!(stream.peer_cred().unwrap().uid(), uid);
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[tokio::test]
+async fn pid_predicate() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("pid.sock");
+
+    let pid = std::process::id() as i32;
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    server.set_access_policy(AccessPolicy::new().pid(move |peer| peer != pid));
+    let _rejected = UnixStream::connect(&sock_path).await.unwrap();
+    assert!(tokio::time::timeout(Duration::from_millis(100), server.accept()).await.is_err());
+
+    server.set_access_policy(AccessPolicy::new().pid(move |peer| peer == pid));
+    let _client = UnixStream::connect(&sock_path).await.unwrap();
+    let (stream, _) = server.accept().await.unwrap();
+    assert_eq!(stream.peer_cred().unwrap().pid(), Some(pid));
+}
@@ -0,0 +1,67 @@
+#![cfg(unix)]
+
+use std::io::{Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd};
+
+use tempfile::Builder;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_agnostic_uds::*;
+
+#[tokio::test]
+async fn std_roundtrip() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("std.sock");
+
+    let mut server = UnixListener::from_std(std::os::unix::net::UnixListener::bind(&sock_path).unwrap()).unwrap();
+    server.set_remove_on_drop(true);
+    let mut client = UnixStream::from_std(std::os::unix::net::UnixStream::connect(&sock_path).unwrap()).unwrap();
+    let (stream, _) = server.accept().await.unwrap();
+
+    // A stream moved to a blocking thread and back keeps working.
+    let fd = stream.as_raw_fd();
+    let mut stream = stream.into_std().unwrap();
+    assert_eq!(stream.as_raw_fd(), fd);
+    let blocking = std::thread::spawn(move || {
+        stream.set_nonblocking(false).unwrap();
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).unwrap();
+        stream.write_all(b"pong").unwrap();
+        stream
+    });
+    client.write_all(b"ping").await.unwrap();
+    let mut buf = [0; 4];
+    client.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"pong");
+
+    let stream = blocking.join().unwrap();
+    stream.set_nonblocking(true).unwrap();
+    let mut stream = UnixStream::from_std(stream).unwrap();
+    client.write_all(b"again").await.unwrap();
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"again");
+
+    // Handing the listener off keeps its socket file.
+    let listener = server.into_std().unwrap();
+    assert!(sock_path.exists());
+    drop(listener);
+}
+
+#[tokio::test]
+async fn raw_roundtrip() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("raw.sock");
+
+    let server = UnixListener::bind(&sock_path).unwrap();
+    let fd = server.as_raw_fd();
+    let mut server = unsafe { UnixListener::from_raw_fd(server.into_raw_fd()) };
+    assert_eq!(server.as_raw_fd(), fd);
+
+    let mut client = UnixStream::connect(&sock_path).await.unwrap();
+    let (stream, _) = server.accept().await.unwrap();
+    let mut stream = unsafe { UnixStream::from_raw_fd(stream.into_raw_fd()) };
+    client.write_all(b"hi").await.unwrap();
+    let mut buf = [0; 2];
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"hi");
+}
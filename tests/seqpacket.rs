@@ -0,0 +1,76 @@
+#![cfg(unix)]
+
+use std::net::Shutdown;
+
+use tempfile::Builder;
+use tokio_agnostic_uds::*;
+
+#[tokio::test]
+async fn keeps_message_boundaries() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("seqpacket.sock");
+
+    let mut server = UnixSeqpacketListener::bind(&sock_path).unwrap();
+    server.set_remove_on_drop(true);
+    assert_eq!(server.local_addr().unwrap().as_pathname(), Some(sock_path.as_path()));
+    let client = UnixSeqpacket::connect(&sock_path).await.unwrap();
+    let (stream, addr) = server.accept().await.unwrap();
+    assert!(addr.is_unnamed());
+    assert_eq!(client.peer_addr().unwrap().as_pathname(), Some(sock_path.as_path()));
+
+    // Each message arrives on its own, however small the messages are.
+    client.send(b"one").await.unwrap();
+    client.send(b"two").await.unwrap();
+    let mut buf = [0; 16];
+    assert_eq!(stream.recv(&mut buf).await.unwrap(), (3, false));
+    assert_eq!(&buf[..3], b"one");
+    assert_eq!(stream.recv(&mut buf).await.unwrap(), (3, false));
+    assert_eq!(&buf[..3], b"two");
+
+    client.shutdown(Shutdown::Write).unwrap();
+    assert_eq!(stream.recv(&mut buf).await.unwrap(), (0, false));
+
+    drop(server);
+    assert!(!sock_path.exists());
+}
+
+#[tokio::test]
+async fn reports_truncation() {
+    let (a, b) = UnixSeqpacket::pair().unwrap();
+    a.send(b"truncated").await.unwrap();
+    a.send(b"whole").await.unwrap();
+
+    // The rest of a message that doesn't fit is discarded, not read next.
+    let mut buf = [0; 5];
+    assert_eq!(b.recv(&mut buf).await.unwrap(), (5, true));
+    assert_eq!(&buf, b"trunc");
+    assert_eq!(b.recv(&mut buf).await.unwrap(), (5, false));
+    assert_eq!(&buf, b"whole");
+}
+
+#[tokio::test]
+async fn recv_waits_for_a_message() {
+    let (a, b) = UnixSeqpacket::pair().unwrap();
+    let recv = tokio::spawn(async move {
+        let mut buf = [0; 16];
+        let (n, truncated) = b.recv(&mut buf).await.unwrap();
+        (buf[..n].to_vec(), truncated)
+    });
+    a.send(b"late").await.unwrap();
+    assert_eq!(recv.await.unwrap(), (b"late".to_vec(), false));
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[tokio::test]
+async fn abstract_name() {
+    let name = format!("tokio-uds-tests-seqpacket-{}", std::process::id());
+    let mut server = UnixSeqpacketListener::bind_abstract(&name).unwrap();
+    assert_eq!(server.local_addr().unwrap().as_abstract_name(), Some(name.as_bytes()));
+
+    let client = UnixSeqpacket::connect_abstract(&name).await.unwrap();
+    let (stream, _) = server.accept().await.unwrap();
+    client.send(b"hello").await.unwrap();
+    let mut buf = [0; 16];
+    assert_eq!(stream.recv(&mut buf).await.unwrap(), (5, false));
+    assert_eq!(stream.peer_cred().unwrap().pid(), Some(std::process::id() as i32));
+}
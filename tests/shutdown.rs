@@ -0,0 +1,102 @@
+#![cfg(unix)]
+
+use std::io;
+use std::time::Duration;
+
+use futures::StreamExt;
+use tempfile::Builder;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::oneshot;
+use tokio_agnostic_uds::*;
+
+#[tokio::test]
+async fn drains_connections() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("drain.sock");
+
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    let handle = server.shutdown_handle();
+    let tasks = handle.clone();
+    let (finish, finished) = oneshot::channel::<()>();
+    let (spawned, task) = oneshot::channel();
+    let accept_loop = tokio::spawn(async move {
+        let (mut stream, _) = server.next().await.unwrap().unwrap();
+        let _ = spawned.send(tasks.spawn(async move {
+            finished.await.unwrap();
+            stream.write_all(b"bye").await.unwrap();
+        }));
+        assert!(server.next().await.is_none(), "stream kept going after shutdown");
+        let err = server.accept().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+    });
+
+    let mut client = UnixStream::connect(&sock_path).await.unwrap();
+    let task = task.await.unwrap();
+    assert_eq!(handle.active_tasks(), 1);
+
+    let shutdown = tokio::spawn(async move { handle.shutdown(Duration::from_secs(10)).await });
+    accept_loop.await.unwrap();
+    finish.send(()).unwrap();
+    assert_eq!(shutdown.await.unwrap(), 0);
+    assert_eq!(task.await.unwrap(), Some(()));
+
+    let mut buf = Vec::new();
+    client.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, b"bye");
+}
+
+#[tokio::test]
+async fn aborts_after_deadline() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("abort.sock");
+
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    let handle = server.shutdown_handle();
+
+    let _client = UnixStream::connect(&sock_path).await.unwrap();
+    let (stream, _) = server.accept().await.unwrap();
+    let (_never, hangs) = oneshot::channel::<()>();
+    let stuck = handle.spawn(async move {
+        let _stream = stream;
+        hangs.await.unwrap();
+    });
+    let quick = handle.spawn(async { 7 });
+    assert_eq!(quick.await.unwrap(), Some(7));
+    assert_eq!(handle.active_tasks(), 1);
+
+    assert_eq!(handle.shutdown(Duration::from_millis(20)).await, 1);
+    assert!(handle.is_shutting_down());
+    assert_eq!(handle.active_tasks(), 0);
+    assert_eq!(stuck.await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn aborts_tasks_spawned_after_deadline() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("late.sock");
+
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    let handle = server.shutdown_handle();
+    let (_never, hangs) = oneshot::channel::<()>();
+    let stuck = handle.spawn(hangs);
+
+    // Tasks spawned during the grace period still get until the deadline.
+    let shutdown = {
+        let handle = handle.clone();
+        tokio::spawn(async move { handle.shutdown(Duration::from_millis(100)).await })
+    };
+    tokio::time::delay_for(Duration::from_millis(20)).await;
+    assert!(handle.is_shutting_down());
+    let graceful = handle.spawn(async { 7 });
+    assert_eq!(graceful.await.unwrap(), Some(7));
+
+    assert_eq!(tokio::time::timeout(Duration::from_secs(5), shutdown).await.unwrap().unwrap(), 1);
+    assert_eq!(stuck.await.unwrap(), None);
+
+    // Past the deadline, nothing can hold shutdown up any more.
+    let (_never, hangs) = oneshot::channel::<()>();
+    let late = handle.spawn(hangs);
+    assert_eq!(tokio::time::timeout(Duration::from_secs(5), late).await.unwrap().unwrap(), None);
+    assert_eq!(handle.active_tasks(), 0);
+    assert_eq!(tokio::time::timeout(Duration::from_secs(5), handle.shutdown(Duration::from_secs(60))).await, Ok(0));
+}
@@ -0,0 +1,92 @@
+#![cfg(unix)]
+
+use std::io;
+
+use tempfile::Builder;
+use tokio_agnostic_uds::*;
+
+mod common;
+
+#[tokio::test]
+async fn replace_stale() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("stale.sock");
+
+    // Nothing there yet.
+    let server = UnixListener::bind_replace_stale(&sock_path).unwrap();
+    drop(server);
+    assert!(sock_path.exists());
+
+    // Left behind by a listener which is gone.
+    let _server = UnixListener::bind_replace_stale(&sock_path).unwrap();
+}
+
+#[tokio::test]
+async fn replace_stale_refuses_live_listener() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("live.sock");
+
+    let _server = UnixListener::bind(&sock_path).unwrap();
+    let err = UnixListener::bind_replace_stale(&sock_path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+    assert!(sock_path.exists());
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[tokio::test]
+async fn replace_stale_does_not_block_on_full_backlog() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("busy.sock");
+
+    let _server = UnixListenerBuilder::new().backlog(1).bind(&sock_path).unwrap();
+    let _clients = common::fill_backlog(&sock_path);
+    let err = UnixListener::bind_replace_stale(&sock_path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+}
+
+#[tokio::test]
+async fn replace_stale_refuses_other_files() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let file_path = dir.path().join("not-a-socket");
+    std::fs::write(&file_path, b"data").unwrap();
+
+    let err = UnixListener::bind_replace_stale(&file_path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    assert_eq!(std::fs::read(&file_path).unwrap(), b"data");
+}
+
+#[tokio::test]
+async fn remove_on_drop() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("owned.sock");
+
+    let server = UnixListener::bind(&sock_path).unwrap();
+    drop(server);
+    assert!(sock_path.exists(), "removed without opting in");
+    std::fs::remove_file(&sock_path).unwrap();
+
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    server.set_remove_on_drop(true);
+    drop(server);
+    assert!(!sock_path.exists());
+
+    let server = UnixListener::bind(&sock_path).unwrap();
+    server.close().unwrap();
+    assert!(!sock_path.exists());
+}
+
+#[tokio::test]
+async fn remove_on_drop_spares_successor() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("successor.sock");
+
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    server.set_remove_on_drop(true);
+    std::fs::remove_file(&sock_path).unwrap();
+    let successor = UnixListener::bind(&sock_path).unwrap();
+
+    drop(server);
+    assert!(sock_path.exists());
+    successor.close().unwrap();
+    assert!(!sock_path.exists());
+}
@@ -0,0 +1,126 @@
+#![cfg(unix)]
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_agnostic_uds::*;
+
+#[tokio::test]
+async fn pair() {
+    let (mut a, mut b) = UnixStream::pair().unwrap();
+    assert!(a.local_addr().unwrap().is_unnamed());
+    assert!(b.peer_addr().unwrap().is_unnamed());
+
+    a.write_all(b"ping").await.unwrap();
+    let mut buf = [0; 4];
+    b.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"ping");
+
+    b.write_all(b"pong").await.unwrap();
+    drop(b);
+    let mut buf = Vec::new();
+    a.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, b"pong");
+}
+
+#[tokio::test]
+async fn split() {
+    let (mut a, mut b) = UnixStream::pair().unwrap();
+    let (mut read, mut write) = a.split();
+
+    write.write_all(b"ping").await.unwrap();
+    let mut buf = [0; 4];
+    b.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"ping");
+
+    b.write_all(b"pong").await.unwrap();
+    read.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"pong");
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn into_split_across_tasks() {
+    let (mut a, b) = UnixStream::pair().unwrap();
+    let (mut read, mut write) = b.into_split();
+
+    let writer = tokio::spawn(async move {
+        write.write_all(b"from writer").await.unwrap();
+        write
+    });
+    let reader = tokio::spawn(async move {
+        let mut buf = [0; 9];
+        read.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"to reader");
+        read
+    });
+
+    a.write_all(b"to reader").await.unwrap();
+    let mut buf = [0; 11];
+    a.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"from writer");
+
+    let mut b = reader.await.unwrap().reunite(writer.await.unwrap()).unwrap();
+    b.write_all(b"reunited").await.unwrap();
+    let mut buf = [0; 8];
+    a.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"reunited");
+}
+
+#[tokio::test]
+async fn reunite_mismatched_halves() {
+    let (a, _) = UnixStream::pair().unwrap();
+    let (b, _) = UnixStream::pair().unwrap();
+    let (read_a, write_a) = a.into_split();
+    let (read_b, write_b) = b.into_split();
+
+    let ReuniteError(read_a, write_b) = read_a.reunite(write_b).unwrap_err();
+    write_a.reunite(read_a).unwrap();
+    read_b.reunite(write_b).unwrap();
+}
+
+#[tokio::test]
+async fn dropping_owned_write_half_shuts_down_writes() {
+    let (mut a, b) = UnixStream::pair().unwrap();
+    let (_read, write) = b.into_split();
+    drop(write);
+
+    let mut buf = Vec::new();
+    a.read_to_end(&mut buf).await.unwrap();
+    assert!(buf.is_empty());
+}
+
+#[tokio::test]
+async fn vectored_write_of_header_and_payload() {
+    use std::io::{IoSlice, IoSliceMut};
+
+    let (a, b) = UnixStream::pair().unwrap();
+
+    // Everything goes out in a single write call.
+    let bufs = [IoSlice::new(b"head"), IoSlice::new(b""), IoSlice::new(b"payload")];
+    let n = futures::future::poll_fn(|cx| a.poll_write_vectored(cx, &bufs)).await.unwrap();
+    assert_eq!(n, 11);
+
+    let (mut header, mut payload) = ([0; 4], [0; 7]);
+    let mut bufs = [IoSliceMut::new(&mut header), IoSliceMut::new(&mut payload)];
+    let n = futures::future::poll_fn(|cx| b.poll_read_vectored(cx, &mut bufs)).await.unwrap();
+    assert_eq!(n, 11);
+    assert_eq!(&header, b"head");
+    assert_eq!(&payload, b"payload");
+}
+
+#[tokio::test]
+async fn buf_chain() {
+    use bytes::buf::BufExt;
+    use bytes::{Buf, BytesMut};
+
+    let (a, mut b) = UnixStream::pair().unwrap();
+    let (_read, mut write) = a.into_split();
+
+    let mut frame = BufExt::chain(&b"head"[..], &b"payload"[..]);
+    assert_eq!(write.write_buf(&mut frame).await.unwrap(), 11);
+    assert!(!frame.has_remaining());
+
+    let mut buf = BytesMut::with_capacity(64);
+    assert_eq!(b.read_buf(&mut buf).await.unwrap(), 11);
+    assert_eq!(&buf[..], b"headpayload");
+
+    assert_eq!(write.write(b"").await.unwrap(), 0);
+}
@@ -0,0 +1,162 @@
+#![cfg(unix)]
+
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+
+use tempfile::Builder;
+use tokio_agnostic_uds::*;
+
+/// Set in the child processes the activation tests re-run themselves in.
+const CHILD: &str = "TOKIO_UDS_TEST_CHILD";
+
+/// Runs the test called `name` again in a child process, as systemd would
+/// start a socket-activated service: with `fd` as descriptor 3 and `vars`
+/// set. `LISTEN_PID` is the child's own unless `vars` says otherwise.
+///
+/// Returns `false` if this already is the child, which should go on to
+/// check what it was passed.
+fn activate(name: &str, fd: RawFd, vars: &[(&str, &str)]) -> bool {
+    if std::env::var_os(CHILD).is_some() {
+        return false;
+    }
+
+    // The shell execs the test binary, so its PID is the one the child gets.
+    let mut command = Command::new("sh");
+    command.arg("-c")
+        .arg("export LISTEN_PID=${LISTEN_PID:-$$}; exec \"$0\" \"$@\"")
+        .arg(std::env::current_exe().unwrap())
+        .args([name, "--exact", "--nocapture", "--test-threads=1"])
+        .env(CHILD, "1")
+        .env_remove("LISTEN_PID")
+        .env_remove("LISTEN_FDS")
+        .env_remove("LISTEN_FDNAMES")
+        .envs(vars.iter().copied());
+    unsafe {
+        command.pre_exec(move || {
+            if fd == 3 {
+                let flags = libc::fcntl(3, libc::F_GETFD);
+                libc::fcntl(3, libc::F_SETFD, flags & !libc::FD_CLOEXEC);
+            } else if libc::dup2(fd, 3) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    let output = command.output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success() && stdout.contains("1 passed"),
+            "child failed:\n{}{}", stdout, String::from_utf8_lossy(&output.stderr));
+    true
+}
+
+#[test]
+fn takes_named_listener() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("activated.sock");
+    let listener = std::os::unix::net::UnixListener::bind(&sock_path).unwrap();
+    if activate("takes_named_listener", listener.as_raw_fd(), &[("LISTEN_FDS", "1"), ("LISTEN_FDNAMES", "api")]) {
+        return;
+    }
+
+    // Descriptor 3 is the parent's listener, bound in its temporary directory.
+    let mut sockets = ActivatedSockets::from_env().unwrap();
+    assert!(std::env::var_os("LISTEN_FDS").is_none());
+    assert!(ActivatedSockets::from_env().unwrap().is_empty());
+    assert_eq!(sockets.len(), 1);
+    assert_eq!(sockets.names().collect::<Vec<_>>(), ["api"]);
+
+    let err = sockets.take_listener("web").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+    let mut runtime = tokio::runtime::Builder::new().basic_scheduler().enable_all().build().unwrap();
+    runtime.block_on(async {
+        let mut server = sockets.take_listener("api").unwrap();
+        let path = server.local_addr().unwrap().as_pathname().unwrap().to_owned();
+        let _client = UnixStream::connect(&path).await.unwrap();
+        server.accept().await.unwrap();
+    });
+    let err = sockets.take_listener("api").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+fn ignores_other_pid() {
+    let (stream, _) = std::os::unix::net::UnixStream::pair().unwrap();
+    if activate("ignores_other_pid", stream.as_raw_fd(), &[("LISTEN_PID", "1"), ("LISTEN_FDS", "1")]) {
+        return;
+    }
+
+    assert!(ActivatedSockets::from_env().unwrap().is_empty());
+    assert!(std::env::var_os("LISTEN_FDS").is_none());
+}
+
+#[test]
+fn rejects_other_files() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let file = std::fs::File::create(dir.path().join("not-a-socket")).unwrap();
+    if activate("rejects_other_files", file.as_raw_fd(), &[("LISTEN_FDS", "1"), ("LISTEN_FDNAMES", "file")]) {
+        return;
+    }
+
+    let mut sockets = ActivatedSockets::from_env().unwrap();
+    let _runtime = tokio::runtime::Builder::new().basic_scheduler().enable_all().build().unwrap();
+    let err = sockets.take_listener("file").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn rejects_non_listening_socket() {
+    let (stream, _peer) = std::os::unix::net::UnixStream::pair().unwrap();
+    if activate("rejects_non_listening_socket", stream.as_raw_fd(), &[("LISTEN_FDS", "1")]) {
+        return;
+    }
+
+    let sockets = ActivatedSockets::from_env().unwrap();
+    assert_eq!(sockets.names().collect::<Vec<_>>(), ["unknown"]);
+    let err = sockets.into_listeners().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn rejects_malformed_variables() {
+    let (stream, _peer) = std::os::unix::net::UnixStream::pair().unwrap();
+    if activate("rejects_malformed_variables", stream.as_raw_fd(), &[]) {
+        return;
+    }
+
+    let cases: &[(&str, Option<&str>)] = &[
+        ("-1", None),
+        ("abc", None),
+        ("4294967295", None),
+        ("2147483647", None),
+        ("1000", None),
+        ("1", Some("a:b")),
+        ("0", Some("a")),
+    ];
+    for &(count, names) in cases {
+        std::env::set_var("LISTEN_PID", std::process::id().to_string());
+        std::env::set_var("LISTEN_FDS", count);
+        match names {
+            Some(names) => std::env::set_var("LISTEN_FDNAMES", names),
+            None => std::env::remove_var("LISTEN_FDNAMES")
+        }
+        let err = ActivatedSockets::from_env().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData, "LISTEN_FDS={} LISTEN_FDNAMES={:?}", count, names);
+    }
+}
+
+#[test]
+fn missing_count_means_no_sockets() {
+    let (stream, _peer) = std::os::unix::net::UnixStream::pair().unwrap();
+    if activate("missing_count_means_no_sockets", stream.as_raw_fd(), &[("LISTEN_FDNAMES", "api")]) {
+        return;
+    }
+
+    // `LISTEN_PID` is ours, but `LISTEN_FDS` isn't set at all.
+    assert!(std::env::var_os("LISTEN_PID").is_some());
+    assert!(ActivatedSockets::from_env().unwrap().is_empty());
+    assert!(std::env::var_os("LISTEN_FDNAMES").is_none());
+}
@@ -0,0 +1,22 @@
+#![cfg(unix)]
+
+use tempfile::Builder;
+use tokio_agnostic_uds::*;
+
+#[tokio::test]
+async fn peer_cred() {
+    let dir = Builder::new().prefix("tokio-uds-tests").tempdir().unwrap();
+    let sock_path = dir.path().join("cred.sock");
+
+    let mut server = UnixListener::bind(&sock_path).unwrap();
+    let client = UnixStream::connect(&sock_path).await.unwrap();
+    let (stream, _) = server.accept().await.unwrap();
+
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    for cred in &[stream.peer_cred().unwrap(), client.peer_cred().unwrap()] {
+        assert_eq!(cred.uid(), uid);
+        assert_eq!(cred.gid(), gid);
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(cred.pid(), Some(std::process::id() as i32));
+    }
+}